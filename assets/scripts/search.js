@@ -0,0 +1,88 @@
+// novos built-in search.
+// Looks for <input id="novos-search-input"> and renders matches into
+// <div id="novos-search-results">. Requires `site.generate_search = true`.
+(function () {
+    const input = document.getElementById('novos-search-input');
+    const results = document.getElementById('novos-search-results');
+    if (!input || !results) return;
+
+    // `window.novosSearch` is written by novos at the top of this file; `data-index`
+    // and `data-encoding` on the script tag override it.
+    const script = document.currentScript;
+    const config = Object.assign(
+        { index: 'search.json', encoding: 'identity' },
+        window.novosSearch,
+        script ? script.dataset : {}
+    );
+    const indexUrl = new URL(config.index, script ? script.src : window.location.href);
+    let index = null;
+
+    // A gzip index is inflated here unless the host already served it with
+    // `Content-Encoding: gzip`, in which case the browser has inflated it and the
+    // body no longer starts with the gzip magic bytes.
+    function parse(buffer) {
+        const bytes = new Uint8Array(buffer);
+        if (config.encoding === 'gzip' && bytes[0] === 0x1f && bytes[1] === 0x8b) {
+            const stream = new Blob([bytes]).stream().pipeThrough(new DecompressionStream('gzip'));
+            return new Response(stream).json();
+        }
+        return JSON.parse(new TextDecoder().decode(bytes));
+    }
+
+    function load() {
+        if (index) return Promise.resolve(index);
+        return fetch(indexUrl)
+            .then((res) => res.arrayBuffer())
+            .then(parse)
+            .then((data) => (index = data));
+    }
+
+    // Subsequence match: every query character must appear in order.
+    function fuzzy(haystack, needle) {
+        let i = 0;
+        for (const ch of haystack) {
+            if (ch === needle[i]) i++;
+            if (i === needle.length) return true;
+        }
+        return needle.length === 0;
+    }
+
+    function score(entry, query) {
+        const title = (entry.title || '').toLowerCase();
+        const tags = (entry.tags || []).join(' ').toLowerCase();
+        const content = (entry.content || entry.snippet || '').toLowerCase();
+        if (title.includes(query)) return 3;
+        if (tags.includes(query)) return 2;
+        if (content.includes(query)) return 1;
+        if (fuzzy(title, query)) return 0.5;
+        return 0;
+    }
+
+    function render(matches) {
+        results.innerHTML = '';
+        for (const entry of matches) {
+            const item = document.createElement('article');
+            item.className = 'novos-search-result';
+            const link = document.createElement('a');
+            link.href = entry.permalink || (entry.slug + '.html');
+            link.textContent = entry.title;
+            const snippet = document.createElement('p');
+            snippet.textContent = entry.snippet || '';
+            item.append(link, snippet);
+            results.appendChild(item);
+        }
+    }
+
+    input.addEventListener('input', () => {
+        const query = input.value.trim().toLowerCase();
+        if (!query) return render([]);
+        load().then((entries) => {
+            const matches = entries
+                .map((entry) => ({ entry, score: score(entry, query) }))
+                .filter((m) => m.score > 0)
+                .sort((a, b) => b.score - a.score)
+                .map((m) => m.entry);
+            render(matches.slice(0, 20));
+        });
+    });
+})();
@@ -4,12 +4,18 @@
 //! It leverages Rust's concurrency model via `Rayon` for high-performance 
 //! transformation of Markdown and Sass into optimized, minified web assets.
 
-use crate::{config::Config, parser, rss, models::Post};
+use crate::{config::{CollectionSettings, Config}, dates, hooks, links, logger, parser, rss, sitemap, models::{MenuEntry, Post}, seo::SeoMeta};
+use crate::cache::{self, BuildCache, BuildState, STATE_DIR};
+use crate::manifest::{Manifest, PageEntry};
+use crate::progress::Progress;
+use crate::shortcodes::Shortcodes;
+use crate::timings::Timings;
 use rayon::prelude::*;
-use serde_json::{json, Value};
-use minify_html::{minify, Cfg};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use minify_html::Cfg;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs, io,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
@@ -17,19 +23,38 @@ use std::{
 };
 
 // High-performance syntax highlighting
-use syntect::highlighting::{ThemeSet, Theme};
-use syntect::parsing::SyntaxSet;
+use crate::parser::{PageFeatures, RenderOptions};
+
+// Standalone CSS minification and prefixing
+use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
+use lightningcss::targets::{Browsers, Targets};
+
+// Pre-compression
+use flate2::{write::GzEncoder, Compression};
+use std::io::Write;
+
+// Static copy filtering
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 
 // Media processing and text manipulation
 use webp::Encoder;
 use regex::Regex;
 
+/// Default WebSocket route for live-reload, overridable with `novos serve --live-path`.
+pub const DEFAULT_LIVE_RELOAD_PATH: &str = "/novos/live";
+
 /// Injected script for the `novos serve` command.
 /// Provides a lightweight WebSocket client to trigger browser refreshes.
+/// The route is read from `data-path` so proxies and custom paths work, and the
+/// scheme follows the page (`wss://` under HTTPS).
 const LIVE_RELOAD_SCRIPT: &str = r#"
-<script id="novos-live-reload">
+<script id="novos-live-reload" data-path="{{path}}">
     (function() {
-        const socket = new WebSocket('ws://' + window.location.host + '/novos/live');
+        const el = document.getElementById('novos-live-reload');
+        const path = (el && el.dataset.path) || '/novos/live';
+        const host = (el && el.dataset.host) || window.location.host;
+        const scheme = window.location.protocol === 'https:' ? 'wss://' : 'ws://';
+        const socket = new WebSocket(scheme + host + path);
         socket.onmessage = (event) => {
             if (event.data === 'reload') {
                 console.log('novos: Change detected, reloading...');
@@ -41,23 +66,34 @@ const LIVE_RELOAD_SCRIPT: &str = r#"
 </script>
 "#;
 
+/// Renders `<pre class="mermaid">` blocks client-side.
+const MERMAID_SCRIPT: &str = r#"<script type="module" id="novos-mermaid">
+    import mermaid from "{{src}}";
+    mermaid.initialize({ startOnLoad: true });
+</script>
+"#;
+
+const DEFAULT_MERMAID_SCRIPT: &str = "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+
+/// Vanilla JS client for `search.json`, written when `site.generate_search_ui` is set.
+const SEARCH_SCRIPT: &str = include_str!("../assets/scripts/search.js");
+
 /// Internal helper to render an index file.
 /// This centralizes the logic for both the main homepage and paginated sub-pages.
 fn render_index_file(
-    tera: &tera::Tera,
+    env: &RenderEnv,
     ctx: &tera::Context,
-    dest: PathBuf,
+    dest: &Path,
     config: &Config,
-    is_dev: bool
 ) -> io::Result<()> {
     // Attempt to render the 'index.html' template
-    match tera.render("index.html", ctx) {
+    match env.tera.render("index.html", ctx) {
         Ok(rendered) => {
             // Apply minification and dev-scripts
-            let final_html = process_html(rendered, config.build.minify_html, is_dev);
-            fs::write(dest, final_html)
+            let final_html = env.finish_html(rendered, config, config.build.minify_html);
+            env.emit(dest, final_html)
         },
-        Err(e) => Err(io::Error::new(io::ErrorKind::Other, format!("Tera Error (index.html): {}", e))),
+        Err(e) => Err(io::Error::other(format!("Tera Error (index.html): {}", e))),
     }
 }
 
@@ -79,45 +115,282 @@ fn resolve_path(relative_path: &str, theme_dir: &Option<PathBuf>) -> PathBuf {
     project_path.to_path_buf()
 }
 
-/// Minifies HTML and optionally injects the live-reload WebSocket script.
+/// Prefixes root-relative `href`/`src` attributes with `config.base`.
+///
+/// Absolute URLs, protocol-relative URLs, anchors and paths already under `base`
+/// are left alone, so running the pass twice never double-prefixes.
+fn rewrite_base_links(html: &str, base: &str) -> String {
+    let base = format!("/{}", base.trim_matches('/'));
+    if base == "/" {
+        return html.to_string();
+    }
+
+    let link_re = Regex::new(r#"(?i)(\s(?:href|src)\s*=\s*["'])(/[^"']*)(["'])"#).unwrap();
+    link_re.replace_all(html, |caps: &regex::Captures| {
+        let path = &caps[2];
+        let already_prefixed = path == base || path.starts_with(&format!("{}/", base));
+        if path.starts_with("//") || already_prefixed {
+            caps[0].to_string()
+        } else {
+            format!("{}{}{}{}", &caps[1], base, path, &caps[3])
+        }
+    }).into_owned()
+}
+
+/// Inserts the live-reload script before the last `</body>` (case-insensitive),
+/// falling back to `</html>` and finally the end of the document. Pages that
+/// already carry the `novos-live-reload` marker are left untouched.
+fn inject_live_reload(html: &mut String, script: &str) {
+    if html.contains("id=\"novos-live-reload\"") {
+        return;
+    }
+    insert_before_body_end(html, script);
+}
+
+/// Keeps search engines out of preview builds: adds a robots `noindex` tag right
+/// after `<head>`, or at the top of documents without one.
+fn inject_noindex(html: &mut String) {
+    const TAG: &str = "<meta name=\"robots\" content=\"noindex\">";
+    let lower = html.to_ascii_lowercase();
+    let head_end = lower
+        .match_indices("<head")
+        .map(|(start, _)| start)
+        .find(|&start| matches!(lower.as_bytes().get(start + 5), Some(b'>' | b' ' | b'\t' | b'\n' | b'\r')))
+        .and_then(|start| lower[start..].find('>').map(|end| start + end + 1));
+    match head_end {
+        Some(pos) => html.insert_str(pos, TAG),
+        None => html.insert_str(0, TAG),
+    }
+}
+
+/// Adds the mermaid.js loader to pages with diagrams, once per page.
+fn inject_mermaid(html: &mut String, src: &str) {
+    if !html.contains(r#"<pre class="mermaid">"#) || html.contains("id=\"novos-mermaid\"") {
+        return;
+    }
+    let script = MERMAID_SCRIPT.replace("{{src}}", &crate::seo::escape_attr(src));
+    insert_before_body_end(html, &script);
+}
+
+fn insert_before_body_end(html: &mut String, script: &str) {
+    // ASCII lowercasing keeps byte offsets identical to the original string.
+    let lower = html.to_ascii_lowercase();
+    let anchor = lower.rfind("</body>").or_else(|| lower.rfind("</html>"));
+    match anchor {
+        Some(pos) => html.insert_str(pos, script),
+        None => html.push_str(script),
+    }
+}
+
+/// Minifies HTML and optionally injects the mermaid loader and live-reload WebSocket script.
 /// 
 /// Uses `minify-html` for extremely fast, spec-compliant minification.
-fn process_html(mut html: String, should_minify: bool, is_dev: bool) -> String {
-    if is_dev {
-        // Inject script before the closing body tag or at the end
-        if let Some(pos) = html.find("</body>") {
-            html.insert_str(pos, LIVE_RELOAD_SCRIPT);
-        } else {
-            html.push_str(LIVE_RELOAD_SCRIPT);
+///
+/// `minify` is `build.minify_html`, or an item's `minify` frontmatter override.
+/// Only HTML pages come through here; feeds and the sitemap are written verbatim.
+fn process_html(mut html: String, config: &Config, live_reload: Option<&str>, minify: bool) -> String {
+    if config.build.rewrite_base_links {
+        html = rewrite_base_links(&html, &config.base);
+    }
+
+    if config.build.mermaid {
+        let src = config.build.mermaid_script.as_deref().unwrap_or(DEFAULT_MERMAID_SCRIPT);
+        if !src.is_empty() {
+            inject_mermaid(&mut html, src);
         }
     }
 
-    if !should_minify {
+    if let Some(path) = live_reload {
+        let script = LIVE_RELOAD_SCRIPT.replace("{{path}}", &crate::seo::escape_attr(path));
+        inject_live_reload(&mut html, &script);
+    }
+
+    if !minify {
         return html;
     }
 
+    // Data blocks (JSON-LD, import maps, templates) must not go through the JS minifier.
+    let (masked, data_blocks) = mask_data_scripts(&html);
+
     let mut cfg = Cfg::new();
     cfg.minify_js = true;
     cfg.minify_css = true;
     cfg.keep_comments = false;
      
-    let minified = minify(html.as_bytes(), &cfg);
-    String::from_utf8(minified).unwrap_or(html)
-}
-
-/// Standard recursive directory copy.
-fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()> {
-    fs::create_dir_all(&dst)?;
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let ty = entry.file_type()?;
-        if ty.is_dir() {
-            copy_dir_all(entry.path(), dst.as_ref().join(entry.file_name()))?;
+    let minified = minify_html::minify(masked.as_bytes(), &cfg);
+    match String::from_utf8(minified) {
+        Ok(mut out) => {
+            for (i, body) in data_blocks.iter().enumerate() {
+                out = out.replacen(&data_script_placeholder(i), body, 1);
+            }
+            out
+        }
+        Err(_) => html,
+    }
+}
+
+fn data_script_placeholder(i: usize) -> String {
+    format!("__novos_data_script_{}__", i)
+}
+
+/// Swaps the body of every non-JavaScript `<script type="...">` for a placeholder
+/// that survives minification, returning the masked HTML and the bodies to restore.
+///
+/// JSON bodies (`application/ld+json`, `application/json`, `importmap`) are compacted
+/// with serde_json; anything else, or JSON that doesn't parse, is kept verbatim.
+fn mask_data_scripts(html: &str) -> (String, Vec<String>) {
+    let script_re = Regex::new(r#"(?is)(<script\b([^>]*)>)(.*?)(</script\s*>)"#).unwrap();
+    let type_re = Regex::new(r#"(?i)\btype\s*=\s*["']?([^"'\s>]+)"#).unwrap();
+    let mut bodies = Vec::new();
+
+    let masked = script_re.replace_all(html, |caps: &regex::Captures| {
+        let kind = type_re
+            .captures(&caps[2])
+            .map(|t| t[1].to_ascii_lowercase())
+            .unwrap_or_default();
+        let is_js = matches!(
+            kind.as_str(),
+            "" | "module" | "text/javascript" | "application/javascript" | "text/ecmascript" | "application/ecmascript"
+        );
+        if is_js {
+            return caps[0].to_string();
+        }
+
+        let body = &caps[3];
+        let is_json = kind == "importmap" || kind.ends_with("json");
+        let compact = is_json
+            .then(|| serde_json::from_str::<Value>(body).ok())
+            .flatten()
+            .map(|v| v.to_string())
+            // Re-serializing can unescape `<\/script>`; never emit a closing tag mid-block.
+            .filter(|json| !json.to_ascii_lowercase().contains("</script"));
+        bodies.push(compact.unwrap_or_else(|| body.to_string()));
+        format!("{}{}{}", &caps[1], data_script_placeholder(bodies.len() - 1), &caps[4])
+    });
+    (masked.into_owned(), bodies)
+}
+
+/// Static trees with at least this many files are copied on the rayon pool;
+/// below it, thread hand-off costs more than the copies themselves.
+const PARALLEL_COPY_MIN_FILES: usize = 256;
+
+/// Standard recursive directory copy, skipping paths (relative to `src`) matched by `exclude`.
+///
+/// With `follow_symlinks`, linked files and directories are copied as regular content;
+/// a directory reached twice through links is only copied once, which also breaks cycles.
+/// Otherwise symlinks are recreated as links (copied as files where the platform can't).
+/// `fs::copy` carries the permission bits over, so executables stay executable.
+///
+/// The walk (and every directory creation) stays sequential; the file copies it
+/// collects run in parallel once there are [PARALLEL_COPY_MIN_FILES] of them.
+fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>, exclude: &GlobSet, follow_symlinks: bool) -> io::Result<()> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    if let Ok(root) = src.canonicalize() {
+        visited.insert(root);
+    }
+    fs::create_dir_all(dst)?;
+
+    // (source, target, is_symlink), each with its parent directory already in place.
+    let mut copies: Vec<(PathBuf, PathBuf, bool)> = Vec::new();
+    let mut walker = walkdir::WalkDir::new(src).min_depth(1).follow_links(follow_symlinks).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.loop_ancestor().is_some() => {
+                log::warn!("skipping symlink loop at {}", e.path().map_or(String::new(), |p| p.display().to_string()));
+                continue;
+            }
+            Err(e) => return Err(io::Error::from(e)),
+        };
+        let rel = entry.path().strip_prefix(src).unwrap_or(entry.path());
+        if exclude.is_match(rel) {
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+        let target = dst.join(rel);
+
+        if entry.file_type().is_dir() {
+            if entry.path_is_symlink() {
+                let canonical = entry.path().canonicalize()?;
+                if !visited.insert(canonical) {
+                    walker.skip_current_dir();
+                    continue;
+                }
+            }
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+
+        // Only reached for links when not following them.
+        let is_symlink = entry.file_type().is_symlink();
+        copies.push((entry.into_path(), target, is_symlink));
+    }
+
+    let copy_one = |(source, target, is_symlink): &(PathBuf, PathBuf, bool)| -> io::Result<()> {
+        if *is_symlink {
+            if target.symlink_metadata().is_ok() {
+                fs::remove_file(target)?;
+            }
+            copy_symlink(source, target)
         } else {
-            fs::copy(entry.path(), dst.as_ref().join(entry.file_name()))?;
+            fs::copy(source, target).map(|_| ())
         }
+    };
+    if copies.len() >= PARALLEL_COPY_MIN_FILES {
+        log::debug!("  copying {} files from {} in parallel", copies.len(), src.display());
+        copies.par_iter().try_for_each(copy_one)
+    } else {
+        copies.iter().try_for_each(copy_one)
     }
-    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(link)?, target)
+}
+
+/// Creating links needs extra privileges on Windows, so copy what the link points at.
+#[cfg(not(unix))]
+fn copy_symlink(link: &Path, target: &Path) -> io::Result<()> {
+    if link.is_dir() {
+        copy_dir_all(link, target, &GlobSet::empty(), true)
+    } else {
+        fs::copy(link, target).map(|_| ())
+    }
+}
+
+/// Compiles a glob list such as `build.static_exclude` (named by `key` in errors).
+/// Patterns without a `/` (e.g. `*.psd`, `.DS_Store`) match at any depth; patterns
+/// with one are anchored to the directory being walked.
+fn glob_set(key: &str, patterns: &[String]) -> io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches('/');
+        let anchored = if pattern.contains('/') { pattern.to_string() } else { format!("**/{}", pattern) };
+        let glob = GlobBuilder::new(&anchored)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{} '{}': {}", key, pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", key, e)))
+}
+
+/// Returns true if `path` points at an asset we produced: either a relative/root-relative
+/// path, or an absolute URL on our own `base_url`. Anything else lives on a foreign host.
+fn is_local_asset(path: &str, base_url: &str) -> bool {
+    let is_external = path.contains("://") || path.starts_with("//");
+    if !is_external {
+        return true;
+    }
+
+    let base = base_url.trim_end_matches('/');
+    !base.is_empty() && (path == base || path.starts_with(&format!("{}/", base)))
 }
 
 /// Regex-based rewriter to swap image extensions in HTML/CSS for WebP.
@@ -133,24 +406,32 @@ fn rewrite_to_webp(content: String, base_url: &str) -> String {
         let path = &caps[2];
         let post = &caps[3];
 
-        let is_external = path.contains("://") || path.starts_with("//");
-        let is_our_domain = path.starts_with(base_url);
-
-        if !is_external || is_our_domain {
+        if is_local_asset(path, base_url) {
             format!("{}{}webp{}", pre, path, post)
         } else {
             caps[0].to_string()
         }
     }).into_owned();
 
-    let mime_re = Regex::new(r#"(?i)image/(?:png|jpeg)"#).unwrap();
-    mime_re.replace_all(&content, "image/webp").into_owned()
+    // MIME types are only swapped inside the tag that references a converted image
+    // (e.g. `<source srcset="a.webp" type="image/png">`), never document-wide, so
+    // JSON-LD, meta tags and inline SVG keep their original values.
+    let tag_re = Regex::new(r#"(?i)<(?:source|link|img|object|embed)\b[^>]*>"#).unwrap();
+    let mime_re = Regex::new(r#"(?i)image/(?:png|jpe?g)"#).unwrap();
+    tag_re.replace_all(&content, |caps: &regex::Captures| {
+        let tag = &caps[0];
+        if tag.to_lowercase().contains(".webp") {
+            mime_re.replace_all(tag, "image/webp").into_owned()
+        } else {
+            tag.to_string()
+        }
+    }).into_owned()
 }
 
 /// Scans the output directory and converts all PNG/JPG assets to WebP.
 /// 
 /// Runs in parallel to utilize multi-core systems.
-fn process_images(config: &Config, verbose: bool) -> io::Result<()> {
+fn process_images(config: &Config) -> io::Result<()> {
     let output_dir = &config.output_dir;
     
     let mut image_paths = Vec::new();
@@ -165,7 +446,9 @@ fn process_images(config: &Config, verbose: bool) -> io::Result<()> {
         }
     }
 
+    let progress = Progress::new("images", image_paths.len());
     image_paths.into_par_iter().for_each(|path| {
+        progress.inc();
         if let Ok(img) = image::open(&path) {
             let encoder = Encoder::from_image(&img).unwrap();
             let webp_data = encoder.encode(75.0); 
@@ -174,9 +457,7 @@ fn process_images(config: &Config, verbose: bool) -> io::Result<()> {
             webp_path.set_extension("webp");
             
             if fs::write(&webp_path, &*webp_data).is_ok() {
-                if verbose {
-                    println!("\x1b[2m  optimized\x1b[0m {}", path.file_name().unwrap().to_str().unwrap());
-                }
+                log::debug!("  optimized {}", path.file_name().unwrap().to_string_lossy());
                 let _ = fs::remove_file(path);
             }
         }
@@ -186,7 +467,11 @@ fn process_images(config: &Config, verbose: bool) -> io::Result<()> {
 }
 
 /// Compiles SCSS/SASS to CSS using the pure-Rust `grass` compiler.
-pub fn compile_sass(config: &Config, theme_dir: &Option<PathBuf>, verbose: bool) -> io::Result<()> {
+///
+/// `grass` cannot emit source maps, so in dev mode (or with `build.sass_source_map`)
+/// the output is forced to the expanded style and prefixed with its source path
+/// to keep devtools output traceable back to the original `.scss` file.
+pub fn compile_sass(config: &Config, theme_dir: &Option<PathBuf>, is_dev: bool) -> io::Result<()> {
     let sass_dir = resolve_path("sass", theme_dir);
     if !sass_dir.exists() {
         return Ok(());
@@ -195,21 +480,27 @@ pub fn compile_sass(config: &Config, theme_dir: &Option<PathBuf>, verbose: bool)
     let css_dir = config.output_dir.join("css");
     fs::create_dir_all(&css_dir)?;
 
+    let debug_output = is_dev || config.build.sass_source_map;
     let style = match config.build.sass_style.as_str() {
-        "compressed" => grass::OutputStyle::Compressed,
+        "compressed" if !debug_output => grass::OutputStyle::Compressed,
         _ => grass::OutputStyle::Expanded,
     };
     
-    let mut options = grass::Options::default().style(style);
+    // The sass root is a load path so nested stylesheets can `@use` siblings from the top.
+    let mut options = grass::Options::default().style(style).load_path(&sass_dir);
     if let Some(td) = theme_dir {
         options = options.load_path(td.join("sass"));
     }
 
-    for entry in fs::read_dir(sass_dir)? {
-        let entry = entry?;
+    // Walk recursively so stylesheets in sub-directories (e.g. `sass/pages/home.scss`)
+    // are compiled too, mirroring their relative location under `css/`.
+    for entry in walkdir::WalkDir::new(&sass_dir).into_iter().filter_map(|e| e.ok()) {
         let path = entry.path();
+        if !entry.file_type().is_file() {
+            continue;
+        }
 
-        if path.extension().map_or(false, |ext| ext == "scss" || ext == "sass") {
+        if path.extension().is_some_and(|ext| ext == "scss" || ext == "sass") {
             let file_name = path.file_name().unwrap().to_str().unwrap();
             
             // Skip partials (files starting with underscore)
@@ -217,23 +508,30 @@ pub fn compile_sass(config: &Config, theme_dir: &Option<PathBuf>, verbose: bool)
                 continue;
             }
 
-            if verbose {
-                println!("\x1b[2m  compiling\x1b[0m {}", file_name);
-            }
+            let rel_path = path.strip_prefix(&sass_dir).unwrap_or(path);
 
-            match grass::from_path(&path, &options) {
+            log::debug!("  compiling {}", rel_path.display());
+
+            match grass::from_path(path, &options) {
                 Ok(mut css) => {
-                    let mut out_path = css_dir.join(file_name);
+                    let mut out_path = css_dir.join(rel_path);
                     out_path.set_extension("css");
+                    if let Some(parent) = out_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
                     
                     if config.build.convert_to_webp {
                         css = rewrite_to_webp(css, &config.base_url);
                     }
 
+                    if debug_output {
+                        css = format!("/* source: {} */\n{}", path.display(), css);
+                    }
+
                     fs::write(out_path, css)?;
                 }
                 Err(e) => {
-                    return Err(io::Error::new(io::ErrorKind::Other, format!("Sass Error: {}", e)));
+                    return Err(io::Error::other(format!("Sass Error: {}", e)));
                 }
             }
         }
@@ -241,6 +539,118 @@ pub fn compile_sass(config: &Config, theme_dir: &Option<PathBuf>, verbose: bool)
     Ok(())
 }
 
+/// Post-processes every `.css` file in the output directory with `lightningcss`.
+///
+/// Handles minification (`build.minify_css`) and vendor prefixing (`build.browser_targets`)
+/// for both compiled Sass and stylesheets copied from `static_dir`.
+fn process_css(config: &Config) -> io::Result<()> {
+    let targets = if config.build.browser_targets.is_empty() {
+        Targets::default()
+    } else {
+        let browsers = Browsers::from_browserslist(&config.build.browser_targets)
+            .map_err(|e| io::Error::other(format!("Invalid browser_targets: {}", e)))?;
+        Targets::from(browsers.unwrap_or_default())
+    };
+
+    let css_paths: Vec<PathBuf> = walkdir::WalkDir::new(&config.output_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "css"))
+        .collect();
+
+    css_paths.into_par_iter().try_for_each(|path| {
+        let source = fs::read_to_string(&path)?;
+        let err = |e: String| io::Error::other(format!("CSS Error ({}): {}", path.display(), e));
+
+        let mut sheet = StyleSheet::parse(&source, ParserOptions::default()).map_err(|e| err(e.to_string()))?;
+        sheet.minify(MinifyOptions { targets, ..Default::default() }).map_err(|e| err(e.to_string()))?;
+        let output = sheet
+            .to_css(PrinterOptions { minify: config.build.minify_css, targets, ..Default::default() })
+            .map_err(|e| err(e.to_string()))?;
+
+        log::debug!("  processed {}", path.display());
+        fs::write(&path, output.code)
+    })
+}
+
+/// Extensions worth pre-compressing; images and fonts are already compressed.
+const COMPRESSIBLE_EXTENSIONS: &[&str] = &["html", "css", "js", "json", "xml", "svg", "txt"];
+
+/// Files smaller than this gain nothing from compression.
+const PRECOMPRESS_MIN_BYTES: u64 = 1024;
+
+/// Writes `.gz` / `.br` siblings for every text asset in the output directory.
+fn precompress_outputs(config: &Config) -> io::Result<()> {
+    let gzip = config.build.precompress.iter().any(|f| f == "gzip");
+    let brotli = config.build.precompress.iter().any(|f| f == "brotli");
+
+    let paths: Vec<PathBuf> = walkdir::WalkDir::new(&config.output_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.metadata().is_ok_and(|m| m.len() >= PRECOMPRESS_MIN_BYTES))
+        .map(|e| e.into_path())
+        .filter(|p| {
+            p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| COMPRESSIBLE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        })
+        .collect();
+
+    paths.into_par_iter().try_for_each(|path| {
+        let data = fs::read(&path)?;
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+        if gzip {
+            fs::write(path.with_file_name(format!("{}.gz", file_name)), gzip_bytes(&data)?)?;
+        }
+
+        if brotli {
+            let mut out = Vec::new();
+            {
+                let mut writer = brotli::CompressorWriter::new(&mut out, 4096, 11, 22);
+                writer.write_all(&data)?;
+            }
+            fs::write(path.with_file_name(format!("{}.br", file_name)), out)?;
+        }
+
+        log::debug!("  compressed {}", file_name);
+        Ok(())
+    })
+}
+
+/// Deletes outputs of the previous build whose source is gone or now renders elsewhere,
+/// along with their precompressed copies. Paths this build rendered or a static
+/// directory provides are kept.
+fn remove_orphans(config: &Config, theme_dir: &Option<PathBuf>, previous: &BuildState, outputs: &BTreeMap<PathBuf, BTreeSet<PathBuf>>) {
+    let current: HashSet<&PathBuf> = outputs.values().flatten().collect();
+    let static_dirs: Vec<PathBuf> = [theme_dir.as_ref().map(|td| td.join("static")), Some(config.static_dir.clone())]
+        .into_iter()
+        .flatten()
+        .collect();
+    for rel in previous.orphans(outputs) {
+        let file_name = rel.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let copies = [rel.clone(), rel.with_file_name(format!("{}.gz", file_name)), rel.with_file_name(format!("{}.br", file_name))];
+        for stale in copies.iter().filter(|p| !current.contains(p)) {
+            if static_dirs.iter().any(|dir| dir.join(stale).exists()) {
+                continue;
+            }
+            let path = config.output_dir.join(stale);
+            if fs::remove_file(&path).is_ok() {
+                log::debug!("  removed orphan {}", path.display());
+            }
+        }
+    }
+}
+
+fn gzip_bytes(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
 /// Loads all TOML/JSON files from `data/` and `themes/<name>/data/`.
 fn load_data_dir(theme_dir: &Option<PathBuf>) -> Value {
     let mut data_map = serde_json::Map::new();
@@ -251,21 +661,21 @@ fn load_data_dir(theme_dir: &Option<PathBuf>) -> Value {
     ];
 
     for path in data_sources.into_iter().flatten() {
-        if path.exists() {
-            if let Ok(entries) = fs::read_dir(path) {
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let p = entry.path();
-                    if let Some(ext) = p.extension().and_then(|s| s.to_str()) {
-                        let stem = p.file_stem().unwrap().to_str().unwrap().to_string();
-                        let content = fs::read_to_string(&p).unwrap_or_default();
-
-                        let val: Value = match ext {
-                            "toml" => toml::from_str(&content).unwrap_or(json!({})),
-                            "json" => serde_json::from_str(&content).unwrap_or(json!({})),
-                            _ => continue,
-                        };
-                        data_map.insert(stem, val);
-                    }
+        if path.exists()
+            && let Ok(entries) = fs::read_dir(path)
+        {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let p = entry.path();
+                if let Some(ext) = p.extension().and_then(|s| s.to_str()) {
+                    let stem = p.file_stem().unwrap().to_str().unwrap().to_string();
+                    let content = fs::read_to_string(&p).unwrap_or_default();
+
+                    let val: Value = match ext {
+                        "toml" => toml::from_str(&content).unwrap_or(json!({})),
+                        "json" => serde_json::from_str(&content).unwrap_or(json!({})),
+                        _ => continue,
+                    };
+                    data_map.insert(stem, val);
                 }
             }
         }
@@ -273,240 +683,1371 @@ fn load_data_dir(theme_dir: &Option<PathBuf>) -> Value {
     Value::Object(data_map)
 }
 
-/// The orchestrator of the `novos` build.
-pub fn perform_build(
-    config: &Config,
-    last_run_mu: Arc<Mutex<SystemTime>>,
-    verbose: bool,
-    is_dev: bool,
-) -> io::Result<()> {
-    let start = Instant::now();
-    let lr = *last_run_mu.lock().unwrap();
-
-    let theme_dir = config.theme.as_ref().map(|t| PathBuf::from("themes").join(t));
-
-    // --- STEP 0: TEMPLATE INITIALIZATION ---
-    let mut tera = parser::init_tera("templates"); 
-    if let Some(td) = &theme_dir {
-        let theme_templates_glob = td.join("templates/**/*");
-        if let Some(glob_str) = theme_templates_glob.to_str() {
-            if let Ok(theme_tera) = tera::Tera::new(glob_str) {
-                // Merge theme templates into our Tera instance
-                tera.extend(&theme_tera).ok();
+/// Reads and parses every `.md` file under `dir` (including subfolders) in parallel.
+///
+/// Files whose mtime hasn't advanced since the last build are served from `cache`.
+fn load_markdown_dir(dir: &Path, ignore: &GlobSet, zone: &dates::Zone, lr: SystemTime, cache: &BuildCache) -> io::Result<Vec<Post>> {
+    let mut paths = Vec::new();
+    if dir.exists() {
+        // Ignored folders (`_templates/`) are pruned along with everything inside them.
+        let walker = walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_ignored_content(e.path(), dir, ignore))
+            .filter_map(|e| e.ok());
+        for e in walker {
+            let p = e.into_path();
+            if p.is_file() && p.extension().map(|s| s == "md").unwrap_or(false) {
+                paths.push(p);
             }
         }
     }
 
-    // --- STEP 1: ASSET PIPELINE ---
-    if config.build.clean_output {
-        if verbose { println!("\x1b[2m[1/5]\x1b[0m Cleaning output directory..."); }
-        if config.output_dir.exists() {
-            let _ = fs::remove_dir_all(&config.output_dir);
+    let mut cascades = HashMap::new();
+    for p in &paths {
+        if let Some(parent) = p.parent() {
+            cascade_for(dir, parent, &mut cascades);
         }
     }
-    
-    fs::create_dir_all(&config.output_dir)?;
-    let posts_out_path = config.output_dir.join(&config.posts_outdir);
-    fs::create_dir_all(&posts_out_path)?;
 
-    // Copy static assets: Theme first, then Project (Project overrides Theme)
-    if let Some(td) = &theme_dir {
-        let theme_static = td.join("static");
-        if theme_static.exists() {
-            copy_dir_all(theme_static, &config.output_dir)?;
+    // Parallel parsing of Markdown and Frontmatter
+    Ok(paths
+        .into_par_iter()
+        .filter_map(|p| {
+            let (defaults, defaults_mtime) = p.parent().and_then(|parent| cascades.get(parent)).cloned().unwrap_or_default();
+            // Editing a `_defaults.toml` counts as editing every post beneath it.
+            let own_mt = fs::metadata(&p).and_then(|m| m.modified()).unwrap_or(lr);
+            let mt = defaults_mtime.map_or(own_mt, |d| own_mt.max(d));
+            if let Some(post) = cache.cached_post(&p, mt) {
+                return Some(post);
+            }
+            let raw = match fs::read_to_string(&p) {
+                Ok(raw) => raw,
+                Err(e) => {
+                    log::warn!("{}: {}; skipped", p.display(), e);
+                    return None;
+                }
+            };
+            if parser::frontmatter_is_malformed(&raw) {
+                log::warn!("{}: frontmatter could not be parsed; skipped", p.display());
+                return None;
+            }
+            if parser::frontmatter_is_unterminated(&raw) {
+                log::warn!("{}: frontmatter has no closing '---'; treating the whole file as content", p.display());
+            }
+            let mut post = parser::parse_frontmatter_with_defaults(&raw, p.file_stem().unwrap().to_str().unwrap(), mt, zone, &defaults);
+            post.subdir = p.parent()
+                .and_then(|parent| parent.strip_prefix(dir).ok())
+                .map(|rel| rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+                .unwrap_or_default();
+            post.source_path = p.clone();
+            cache.store_post(p, mt, &post);
+            Some(post)
+        })
+        .collect())
+}
+
+/// Frontmatter defaults for `current` (a directory inside `root`): every `_defaults.toml`
+/// from `root` down to `current` merged in order, so nearer files win key by key.
+/// Also returns the newest of their modification times. Results are memoized in `memo`.
+fn cascade_for(
+    root: &Path,
+    current: &Path,
+    memo: &mut HashMap<PathBuf, (Map<String, Value>, Option<SystemTime>)>,
+) -> (Map<String, Value>, Option<SystemTime>) {
+    if let Some(found) = memo.get(current) {
+        return found.clone();
+    }
+    let (mut defaults, mut newest) = match current.parent() {
+        Some(parent) if current != root && parent.starts_with(root) => cascade_for(root, parent, memo),
+        _ => (Map::new(), None),
+    };
+
+    let file = current.join("_defaults.toml");
+    if let Ok(text) = fs::read_to_string(&file) {
+        match parser::parse_defaults(&text) {
+            Some(own) => {
+                for (key, value) in own {
+                    match (defaults.get_mut(&key), value) {
+                        (Some(Value::Object(inherited)), Value::Object(table)) => inherited.extend(table),
+                        (_, value) => {
+                            defaults.insert(key, value);
+                        }
+                    }
+                }
+                let mtime = fs::metadata(&file).and_then(|m| m.modified()).ok();
+                newest = newest.max(mtime);
+            }
+            None => log::warn!("{}: not a valid TOML table; ignored", file.display()),
         }
     }
-    if config.static_dir.exists() {
-        copy_dir_all(&config.static_dir, &config.output_dir)?;
+
+    memo.insert(current.to_path_buf(), (defaults.clone(), newest));
+    (defaults, newest)
+}
+
+/// Whether `build.content_ignore` excludes a Markdown file. `_index.md` supplies the
+/// homepage prose and is never ignored.
+fn is_ignored_content(path: &Path, dir: &Path, ignore: &GlobSet) -> bool {
+    if path.file_name().is_some_and(|n| n == "_index.md") {
+        return false;
+    }
+    ignore.is_match(path.strip_prefix(dir).unwrap_or(path))
+}
+
+/// Joins a post's author keys against `data/authors.toml` so templates get full
+/// profiles (name, bio, avatar, links). Unknown authors resolve to `{ "name": key }`.
+fn resolve_authors(post: &Post, config: &Config, data: &Value) -> Vec<Value> {
+    let keys = if post.authors.is_empty() && !config.site.author.is_empty() {
+        vec![config.site.author.clone()]
+    } else {
+        post.authors.clone()
+    };
+
+    keys.into_iter()
+        .map(|key| {
+            let mut profile = data
+                .get("authors")
+                .and_then(|a| a.get(&key))
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            if let Some(obj) = profile.as_object_mut() {
+                obj.entry("name").or_insert_with(|| json!(key));
+                obj.insert("id".to_string(), json!(key));
+            }
+            profile
+        })
+        .collect()
+}
+
+/// Where a post or page is written within `config.output_dir`.
+fn output_path(config: &Config, p: &Post) -> PathBuf {
+    config.output_dir.join(config.output_file_for(p))
+}
+
+/// Route of a pagination page under `site.pagination_path`. Page 1 is the site
+/// index unless `site.paginate_first_page` is set.
+fn page_route(config: &Config, page: usize) -> String {
+    if page == 1 && !config.site.paginate_first_page {
+        String::new()
+    } else {
+        format!("{}/{}", config.site.pagination_path.trim_matches('/'), page)
     }
+}
+
+/// Placeholder left behind by `build.expired_stubs`.
+fn expired_stub_html(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"robots\" content=\"noindex\">\n\
+         <title>Gone</title>\n</head>\n<body><p>\"{}\" is no longer available.</p></body>\n</html>\n",
+        crate::seo::escape_attr(title)
+    )
+}
+
+/// Picks the template for a post: its `template` frontmatter if set, else `default`.
+///
+/// A bare name like `landing` also matches `landing.html`. Unknown names produce an
+/// error listing every registered template.
+fn resolve_template<'a>(tera: &'a tera::Tera, requested: Option<&'a str>, default: &'a str) -> Result<&'a str, String> {
+    let Some(name) = requested else {
+        return Ok(default);
+    };
 
-    if config.build.convert_to_webp {
-        if verbose { println!("\x1b[2m[1.5/5]\x1b[0m Optimizing images..."); }
-        process_images(config, verbose)?;
+    if let Some(found) = tera.get_template_names().find(|t| *t == name || *t == format!("{}.html", name)) {
+        return Ok(found);
     }
 
-    // --- STEP 2: STYLESHEETS ---
-    if verbose { println!("\x1b[2m[2/5]\x1b[0m Compiling stylesheets..."); }
-    compile_sass(config, &theme_dir, verbose)?;
+    let mut available: Vec<&str> = tera.get_template_names().collect();
+    available.sort();
+    Err(format!("template '{}' not found; available templates: {}", name, available.join(", ")))
+}
 
-    // --- STEP 3: CONTENT INGESTION ---
-    if verbose { println!("\x1b[2m[3/5]\x1b[0m Processing content..."); }
-    
-    let ps = SyntaxSet::load_defaults_newlines();
-    
-    // Custom Syntax Theme Logic: 
-    // If syntax_theme_path is set, attempt to load that file.
-    // Otherwise, fallback to the internal ThemeSet.
-    let syntax_theme: Theme = if let Some(ref path_str) = config.build.syntax_theme_path {
-        let theme_path = Path::new(path_str);
-        if theme_path.exists() {
-            let mut reader = io::BufReader::new(fs::File::open(theme_path)?);
-            ThemeSet::load_from_reader(&mut reader)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Failed to load custom theme: {}", e)))?
-        } else {
-            // Path provided but not found: Fallback to Ocean Dark
-            let ts = ThemeSet::load_defaults();
-            ts.themes.get("base16-ocean.dark").unwrap().clone()
+/// Accepted values for `site.sort_by`.
+const SORT_ORDERS: [&str; 4] = ["date_desc", "date_asc", "title", "weight"];
+
+/// Orders two posts according to `site.sort_by`; unknown values fall back to newest first.
+fn compare_posts(a: &Post, b: &Post, sort_by: &str) -> std::cmp::Ordering {
+    let newest_first = b.datetime.cmp(&a.datetime);
+    match sort_by {
+        "date_asc" => a.datetime.cmp(&b.datetime),
+        "title" => a.title.to_lowercase().cmp(&b.title.to_lowercase()).then(newest_first),
+        "weight" => a.weight.cmp(&b.weight).then(newest_first),
+        _ => newest_first,
+    }
+}
+
+/// Collects `menu` frontmatter into named menus, each sorted by weight then title.
+fn build_menus(items: &[&Post], config: &Config) -> BTreeMap<String, Vec<MenuEntry>> {
+    let mut menus: BTreeMap<String, Vec<MenuEntry>> = BTreeMap::new();
+    for p in items {
+        for menu in &p.menus {
+            menus.entry(menu.clone()).or_default().push(MenuEntry {
+                title: p.title.clone(),
+                url: config.permalink_for(p),
+                weight: p.weight,
+            });
         }
+    }
+    for entries in menus.values_mut() {
+        entries.sort_by(|a, b| a.weight.cmp(&b.weight).then_with(|| a.title.cmp(&b.title)));
+    }
+    menus
+}
+
+/// Maps an alias URL to the file that should serve it: directory-style and
+/// extensionless aliases get an `index.html`.
+fn alias_output_path(alias: &str) -> PathBuf {
+    let trimmed = alias.trim_start_matches('/');
+    let path = PathBuf::from(trimmed);
+    if trimmed.is_empty() || trimmed.ends_with('/') || path.extension().is_none() {
+        path.join("index.html")
     } else {
-        // No path provided: Load from standard syntect defaults
-        let ts = ThemeSet::load_defaults();
-        ts.themes.get(&config.build.syntax_theme)
-            .cloned()
-            .unwrap_or_else(|| ts.themes.get("base16-ocean.dark").unwrap().clone())
-    };
+        path
+    }
+}
 
-    let global_data = load_data_dir(&theme_dir);
+/// Minimal redirect stub used for `aliases`.
+fn redirect_html(target: &str) -> String {
+    let target = crate::seo::escape_attr(target);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Redirecting...</title>\n\
+         <link rel=\"canonical\" href=\"{0}\">\n<meta http-equiv=\"refresh\" content=\"0; url={0}\">\n\
+         </head>\n<body><a href=\"{0}\">{0}</a></body>\n</html>\n",
+        target
+    )
+}
 
-    let mut post_paths = Vec::new();
-    if config.posts_dir.exists() {
-        for e in fs::read_dir(&config.posts_dir)? {
-            let p = e?.path();
-            if p.extension().map(|s| s == "md").unwrap_or(false) {
-                post_paths.push(p);
+/// One `search.json` record. Borrows from the post so large bodies aren't copied
+/// before serialization.
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    title: &'a str,
+    slug: &'a str,
+    permalink: String,
+    date: &'a str,
+    tags: &'a [String],
+    draft: bool,
+    snippet: String,
+    content: &'a str,
+}
+
+/// Shared, language-independent state for the content rendering phase.
+struct RenderEnv<'a> {
+    tera: &'a tera::Tera,
+    render: RenderOptions<'a>,
+    global_data: &'a Value,
+    shortcodes: Shortcodes,
+    /// Slug -> available languages and their permalinks.
+    translations: &'a HashMap<String, Vec<Value>>,
+    lr: SystemTime,
+    /// Canonical paths of the only sources to re-render, for incremental rebuilds.
+    changed: Option<&'a [PathBuf]>,
+    is_dev: bool,
+    live_reload_path: &'a str,
+    dry_run: bool,
+    /// `build --preview`: every page gets `noindex`.
+    preview: bool,
+    /// Values shared by every template context (`build_time`, `generator`).
+    globals: tera::Context,
+    timings: &'a Timings,
+    /// Output paths recorded instead of written during a dry run.
+    planned: Mutex<Vec<PathBuf>>,
+    render_errors: Mutex<usize>,
+    /// Outputs skipped because the file on disk already had identical contents.
+    unchanged: Mutex<usize>,
+    written: Mutex<usize>,
+    /// Every output rendered (written or already up to date), by source path.
+    outputs: Mutex<BTreeMap<PathBuf, BTreeSet<PathBuf>>>,
+    start: Instant,
+}
+
+impl RenderEnv<'_> {
+    /// The live-reload route to inject, if this is a dev build.
+    fn live_reload(&self) -> Option<&str> {
+        self.is_dev.then_some(self.live_reload_path)
+    }
+
+    /// Post-processes a rendered template: `noindex` for previews, then [process_html].
+    fn finish_html(&self, mut html: String, config: &Config, minify: bool) -> String {
+        if self.preview {
+            inject_noindex(&mut html);
+        }
+        process_html(html, config, self.live_reload(), minify)
+    }
+
+    /// Writes an output file (creating parent directories), or only records it in dry-run mode.
+    fn emit(&self, path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        self.emit_from(Path::new(""), path, contents)
+    }
+
+    /// [emit](Self::emit) for an output rendered from `source`.
+    fn emit_from(&self, source: &Path, path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        self.record(source, path);
+        if self.dry_run {
+            if let Ok(mut planned) = self.planned.lock() {
+                planned.push(path.to_path_buf());
             }
+            return Ok(());
+        }
+        let contents = contents.as_ref();
+        // Identical bytes: leave the file (and its mtime) alone for rsync/CDN diffing.
+        let unchanged = fs::metadata(path).is_ok_and(|m| m.len() == contents.len() as u64)
+            && fs::read(path).is_ok_and(|existing| existing == contents);
+        let counter = if unchanged { &self.unchanged } else { &self.written };
+        if let Ok(mut count) = counter.lock() {
+            *count += 1;
+        }
+        if unchanged {
+            return Ok(());
         }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)
     }
 
-    // Parallel parsing of Markdown and Frontmatter
-    let mut posts: Vec<Post> = post_paths
-        .into_par_iter()
-        .map(|p| {
-            let mt = fs::metadata(&p).and_then(|m| m.modified()).unwrap_or(lr);
-            let raw = fs::read_to_string(&p).unwrap_or_default();
-            parser::parse_frontmatter(&raw, p.file_stem().unwrap().to_str().unwrap(), mt)
-        })
-        .collect();
+    /// Notes that `path` belongs to this build, so it isn't removed as an orphan.
+    fn record(&self, source: &Path, path: &Path) {
+        if let Ok(mut outputs) = self.outputs.lock() {
+            outputs.entry(source.to_path_buf()).or_default().insert(path.to_path_buf());
+        }
+    }
+
+    /// Expands shortcodes and renders an item's Markdown body to HTML.
+    fn render_body(&self, p: &Post, config: &Config) -> (String, PageFeatures) {
+        let expanded = self.shortcodes.resolve_tags(&p.raw_content, &p.source_path);
+        let options = parser::markdown_options(&config.build.markdown);
+        parser::render_markdown_with_features(&expanded, options, &self.render)
+    }
 
-    posts.sort_by(|a, b| b.date.cmp(&a.date));
+    /// Reports a template failure without aborting the rest of the build.
+    fn report_render_error(&self, template: &str, err: tera::Error) {
+        log::error!("Tera Error ({}): {}", template, err);
+        if let Ok(mut count) = self.render_errors.lock() {
+            *count += 1;
+        }
+    }
+}
+
+/// Splits a `my-post.es` stem into slug + language when `site.languages` is configured.
+/// Content without a recognized suffix belongs to the default language.
+fn assign_language(post: &mut Post, config: &Config) {
+    post.lang = config.site.default_language.clone();
+    if let Some((slug, lang)) = post.slug.rsplit_once('.')
+        && config.site.languages.iter().any(|l| l == lang)
+    {
+        post.lang = lang.to_string();
+        post.slug = slug.to_string();
+    }
+}
+
+/// Groups equivalent posts/pages across languages by slug.
+fn build_translations(posts: &[Post], pages: &[Post], config: &Config) -> HashMap<String, Vec<Value>> {
+    let mut map: HashMap<String, Vec<Value>> = HashMap::new();
+    if config.site.languages.is_empty() {
+        return map;
+    }
+    for p in posts.iter().chain(pages) {
+        let lang_config = config.for_language(&p.lang);
+        map.entry(p.slug.clone()).or_default().push(json!({
+            "lang": p.lang,
+            "title": p.title,
+            "permalink": lang_config.permalink_for(p),
+        }));
+    }
+    for entries in map.values_mut() {
+        entries.sort_by(|a, b| a["lang"].as_str().cmp(&b["lang"].as_str()));
+    }
+    map
+}
+
+/// Context values every template receives: when the build ran and what produced it.
+/// `build_time` uses `site.date_format`/`site.locale`, like the `date_format` filter.
+fn build_globals(config: &Config) -> tera::Context {
+    let now = chrono::Utc::now().fixed_offset();
+    let mut globals = tera::Context::new();
+    globals.insert(
+        "build_time",
+        &dates::format_localized(&now, &config.site.date_format, config.site.locale.as_deref()),
+    );
+    globals.insert("build_time_rfc3339", &now.to_rfc3339());
+    globals.insert("generator", &format!("novos v{}", env!("CARGO_PKG_VERSION")));
+    globals
+}
+
+/// Tags of a standalone collection, whose pages live under `<output>/tags/`.
+struct CollectionTags<'p> {
+    terms: BTreeMap<String, Vec<&'p Post>>,
+    slugs: BTreeMap<String, String>,
+    routes: BTreeMap<String, String>,
+    urls: BTreeMap<String, String>,
+}
+
+impl<'p> CollectionTags<'p> {
+    fn new(config: &Config, collection: &CollectionSettings, items: &[&'p Post]) -> Self {
+        let mut terms: BTreeMap<String, Vec<&'p Post>> = BTreeMap::new();
+        for &item in items {
+            for tag in &item.tags {
+                terms.entry(tag.clone()).or_default().push(item);
+            }
+        }
+        let slugs: BTreeMap<String, String> = terms.keys()
+            .map(|t| (t.clone(), parser::slugify(t, &config.build.slugify_mode)))
+            .collect();
+        let routes: BTreeMap<String, String> = slugs.iter()
+            .map(|(t, slug)| {
+                let route = [collection.output_dir(), "tags", slug.as_str()]
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                (t.clone(), route)
+            })
+            .collect();
+        let urls = routes.iter().map(|(t, route)| (t.clone(), config.route_url(route))).collect();
+        CollectionTags { terms, slugs, routes, urls }
+    }
+}
+
+/// Renders posts, pages, taxonomies, indices and feeds for one language subtree.
+fn render_content(
+    config: &Config,
+    lang: &str,
+    posts: &[&Post],
+    pages: &[&Post],
+    collections: &BTreeMap<&str, Vec<&Post>>,
+    env: &RenderEnv,
+) -> io::Result<()> {
+    // `pages/index.md` (or `_index.md`) supplies homepage prose instead of becoming a page.
+    let is_home = |p: &Post| p.subdir.is_empty() && (p.slug == "index" || p.slug == "_index");
+    let home_page = pages.iter().copied().find(|p| is_home(p));
+    let pages: Vec<&Post> = pages.iter().copied().filter(|p| !is_home(p)).collect();
+    let pages = pages.as_slice();
+    let page_content = home_page.map(|p| env.render_body(p, config).0);
 
     // --- STEP 4: TAXONOMY AGGREGATION ---
-    let mut taxonomies: HashMap<String, Vec<&Post>> = HashMap::new();
-    for post in &posts {
+    // Ordered maps throughout, so templates, logs, tags.json and the manifest list
+    // tags identically on every run.
+    let mut taxonomies: BTreeMap<String, Vec<&Post>> = BTreeMap::new();
+    for &post in posts {
         for tag in &post.tags {
             taxonomies.entry(tag.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(post);
         }
     }
 
+    // Display name -> file-safe slug, so "os/linux" is shown as-is but lives at tags/os-linux.html
+    let tag_slugs: BTreeMap<&String, String> = taxonomies.keys().map(|t| (t, parser::slugify(t, &config.build.slugify_mode))).collect();
+    let tag_routes: BTreeMap<&String, String> = tag_slugs.iter().map(|(&t, slug)| (t, format!("tags/{}", slug))).collect();
+    // Display name -> public URL, so templates don't have to know `build.url_style`.
+    let tag_urls: BTreeMap<&String, String> = tag_routes.iter().map(|(&t, route)| (t, config.route_url(route))).collect();
+
+    // Collections outside the index render on their own, each with its own tags.
+    let standalone: Vec<&Post> = collections
+        .iter()
+        .filter(|(name, _)| config.collection(name).is_some_and(|c| !c.in_index))
+        .flat_map(|(_, items)| items.iter().copied())
+        .collect();
+    let collection_tags: BTreeMap<&str, CollectionTags> = config.collections
+        .iter()
+        .filter(|c| !c.in_index)
+        .map(|c| {
+            let items = collections.get(c.name.as_str()).map_or(&[][..], Vec::as_slice);
+            (c.name.as_str(), CollectionTags::new(config, c, items))
+        })
+        .collect();
+
+    let stats = json!({
+        "total_posts": posts.len(),
+        "total_tags": taxonomies.len(),
+        "total_pages": pages.len(),
+        "total_words": posts.iter().chain(pages).map(|p| p.word_count).sum::<usize>(),
+    });
+
+    let site_seo = SeoMeta::for_site(config);
+    let all_items: Vec<&Post> = posts.iter().chain(pages).chain(&standalone).copied().collect();
+    let menus = build_menus(&all_items, config);
+
     // --- STEP 5: RENDERING ---
-    if verbose { println!("\x1b[2m[4/5]\x1b[0m Rendering posts and taxonomies..."); }
+    log::debug!("[4/5] Rendering posts and taxonomies...");
 
-    posts.par_iter().for_each(|p| {
-        let dest = posts_out_path.join(format!("{}.html", p.slug));
-        if p.mtime > lr || !dest.exists() {
-            let body = parser::render_markdown(&p.raw_content, config.build.use_syntect, &ps, &syntax_theme);
+    let page_template = if env.tera.get_template_names().any(|t| t == "page.html") {
+        "page.html"
+    } else {
+        "post.html"
+    };
+
+    // A collection's own `<name>.html` when the theme has one.
+    let collection_templates: HashMap<&str, String> = config.collections
+        .iter()
+        .map(|c| {
+            let own = format!("{}.html", c.name);
+            let template = if env.tera.get_template_names().any(|t| t == own) { own } else { "post.html".to_string() };
+            (c.name.as_str(), template)
+        })
+        .collect();
+
+    let render_item = |p: &Post| {
+        let dest = output_path(config, p);
+        let collection = p.collection.as_deref().and_then(|name| config.collection(name));
+        let default_template = match collection {
+            Some(c) => collection_templates[c.name.as_str()].as_str(),
+            None if p.is_page => page_template,
+            None => "post.html",
+        };
+        // Frontmatter `template` beats the collection's, which beats the default.
+        let requested = p.template.as_deref().or(collection.and_then(|c| c.template.as_deref()));
+        let template = match resolve_template(env.tera, requested, default_template) {
+            Ok(t) => t,
+            Err(msg) => {
+                log::error!("{}: {}", p.source_path.display(), msg);
+                if let Ok(mut count) = env.render_errors.lock() {
+                    *count += 1;
+                }
+                return;
+            }
+        };
+        let stale = match env.changed {
+            Some(changed) => fs::canonicalize(&p.source_path).is_ok_and(|src| changed.contains(&src)),
+            None => p.mtime > env.lr,
+        };
+        if env.dry_run || stale || !dest.exists() {
+            let (body, features) = env.render_body(p, config);
             
-            let mut context = tera::Context::new();
+            let mut context = env.globals.clone();
             context.insert("post", p);
-            context.insert("posts", &posts); 
-            context.insert("taxonomies", &taxonomies);
-            context.insert("data", &global_data);
+            context.insert("posts", posts); 
+            context.insert("pages", pages);
+            context.insert("collections", collections);
+            match p.collection.as_deref().and_then(|name| collection_tags.get(name)) {
+                Some(tags) => {
+                    context.insert("taxonomies", &tags.terms);
+                    context.insert("tag_slugs", &tags.slugs);
+                    context.insert("tag_urls", &tags.urls);
+                }
+                None => {
+                    context.insert("taxonomies", &taxonomies);
+                    context.insert("tag_slugs", &tag_slugs);
+                    context.insert("tag_urls", &tag_urls);
+                }
+            }
+            context.insert("data", env.global_data);
             context.insert("config", config);
             context.insert("content", &body);
+            // So themes only pull in highlighting, math or diagram assets where they're used.
+            context.insert("has_code", &features.has_code);
+            context.insert("has_math", &features.has_math);
+            context.insert("has_mermaid", &features.has_mermaid);
+            context.insert("seo", &SeoMeta::for_post(p, config));
+            context.insert("canonical_url", &p.canonical.clone().unwrap_or_else(|| config.permalink_for(p)));
+            context.insert("authors", &resolve_authors(p, config, env.global_data));
+            context.insert("lang", lang);
+            context.insert("expired", &p.is_expired());
+            context.insert("menus", &menus);
+            context.insert("translations", &env.translations.get(&p.slug));
+            // For `<meta name="robots" content="...">`; null when the item is indexable.
+            context.insert("robots_meta", &p.robots);
 
-            if let Ok(rendered) = tera.render("post.html", &context) {
-                let mut final_html = process_html(rendered, config.build.minify_html, is_dev);
-                if config.build.convert_to_webp {
-                    final_html = rewrite_to_webp(final_html, &config.base_url);
+            match env.tera.render(template, &context) {
+                Ok(rendered) => {
+                    let mut final_html = env.finish_html(rendered, config, p.minify(config));
+                    if config.build.convert_to_webp {
+                        final_html = rewrite_to_webp(final_html, &config.base_url);
+                    }
+                    env.emit_from(&p.source_path, &dest, final_html).ok();
                 }
-                fs::write(dest, final_html).ok();
+                Err(e) => env.report_render_error(template, e),
             }
+        } else {
+            env.record(&p.source_path, &dest);
         }
+    };
+    let phase = Instant::now();
+    let progress = Progress::new("rendering", posts.len() + pages.len() + standalone.len());
+    posts.par_iter().chain(pages.par_iter()).chain(standalone.par_iter()).for_each(|&p| {
+        render_item(p);
+        progress.inc();
     });
+    drop(progress);
+    env.timings.add("post render", phase);
 
+    let phase = Instant::now();
     if !taxonomies.is_empty() {
-        let tax_out_dir = config.output_dir.join("tags");
-        fs::create_dir_all(&tax_out_dir)?;
-
-        taxonomies.par_iter().for_each(|(tag, tagged_posts)| {
-            let mut tax_ctx = tera::Context::new();
+        let sorted: Vec<(&String, &Vec<&Post>)> = taxonomies.iter().collect();
+        sorted.into_par_iter().for_each(|(tag, tagged_posts)| {
+            let mut tax_ctx = env.globals.clone();
             tax_ctx.insert("tag", tag); 
+            tax_ctx.insert("tag_slug", &tag_slugs[tag]);
+            tax_ctx.insert("tag_slugs", &tag_slugs);
+            tax_ctx.insert("tag_urls", &tag_urls);
             tax_ctx.insert("posts", tagged_posts); 
+            tax_ctx.insert("collections", collections);
             tax_ctx.insert("config", config);
-            tax_ctx.insert("data", &global_data);
+            tax_ctx.insert("data", env.global_data);
+            tax_ctx.insert("seo", &site_seo);
+            tax_ctx.insert("lang", lang);
+            tax_ctx.insert("menus", &menus);
+            tax_ctx.insert("stats", &stats);
 
-            let template = if tera.get_template_names().any(|t| t == "tag.html") { 
+            let template = if env.tera.get_template_names().any(|t| t == "tag.html") { 
                 "tag.html" 
             } else { 
                 "index.html" 
             };
             
-            if let Ok(rendered) = tera.render(template, &tax_ctx) {
-                let final_html = process_html(rendered, config.build.minify_html, is_dev);
-                let dest = tax_out_dir.join(format!("{}.html", tag.to_lowercase()));
-                fs::write(dest, final_html).ok();
+            match env.tera.render(template, &tax_ctx) {
+                Ok(rendered) => {
+                    let final_html = env.finish_html(rendered, config, config.build.minify_html);
+                    let dest = config.output_dir.join(config.build.url_style.file(&tag_routes[tag]));
+                    env.emit(&dest, final_html).ok();
+                }
+                Err(e) => env.report_render_error(template, e),
             }
         });
     }
 
+    // Standalone collection tags: `<name>_tag.html`, else the site's tag template.
+    let mut collection_tag_pages: Vec<(&str, &String, &String, &Vec<&Post>)> = Vec::new();
+    for (&name, tags) in &collection_tags {
+        for (tag, tagged) in &tags.terms {
+            collection_tag_pages.push((name, tag, &tags.routes[tag], tagged));
+        }
+    }
+    collection_tag_pages.par_iter().for_each(|&(name, tag, route, tagged)| {
+        let tags = &collection_tags[name];
+        let mut tax_ctx = env.globals.clone();
+        tax_ctx.insert("tag", tag);
+        tax_ctx.insert("tag_slug", &tags.slugs[tag]);
+        tax_ctx.insert("tag_slugs", &tags.slugs);
+        tax_ctx.insert("tag_urls", &tags.urls);
+        tax_ctx.insert("posts", tagged);
+        tax_ctx.insert("collection", name);
+        tax_ctx.insert("collections", collections);
+        tax_ctx.insert("config", config);
+        tax_ctx.insert("data", env.global_data);
+        tax_ctx.insert("seo", &site_seo);
+        tax_ctx.insert("lang", lang);
+        tax_ctx.insert("menus", &menus);
+        tax_ctx.insert("stats", &stats);
+
+        let own = format!("{}_tag.html", name);
+        let template = [own.as_str(), "tag.html"]
+            .into_iter()
+            .find(|t| env.tera.get_template_names().any(|n| n == *t))
+            .unwrap_or("index.html");
+        match env.tera.render(template, &tax_ctx) {
+            Ok(rendered) => {
+                let final_html = env.finish_html(rendered, config, config.build.minify_html);
+                let dest = config.output_dir.join(config.build.url_style.file(route));
+                env.emit(&dest, final_html).ok();
+            }
+            Err(e) => env.report_render_error(template, e),
+        }
+    });
+
+    let mut feeds = Vec::new();
+    let mut manifest_pages: Vec<PageEntry> = posts.iter().chain(pages).chain(&standalone).map(|&p| PageEntry::post(p, config)).collect();
+    let tag_names: Vec<&String> = taxonomies.keys().collect();
+    for &tag in &tag_names {
+        manifest_pages.push(PageEntry::generated("tag", &tag_routes[tag], tag, config));
+    }
+    for &(_, tag, route, _) in &collection_tag_pages {
+        manifest_pages.push(PageEntry::generated("tag", route, tag, config));
+    }
+
+    if config.site.generate_taxonomy_index {
+        let tag_index: Vec<serde_json::Value> = tag_names.iter().map(|&tag| {
+            let tagged = &taxonomies[tag];
+            json!({
+                "name": tag,
+                "slug": tag_slugs[tag],
+                "permalink": tag_urls[tag],
+                "count": tagged.len(),
+                "posts": tagged.iter().map(|p| &p.slug).collect::<Vec<_>>()
+            })
+        }).collect();
+        env.emit(&config.output_dir.join("tags.json"), serde_json::to_string(&tag_index)?)?;
+        feeds.push("tags.json".to_string());
+    }
+
+    env.timings.add("taxonomy", phase);
+
     // --- STEP 6: INDEX & PAGINATION ---
-    if verbose { println!("\x1b[2m[5/5]\x1b[0m Finalizing indices and metadata..."); }
+    log::debug!("[5/5] Finalizing indices and metadata...");
+    let phase = Instant::now();
 
-    if config.site.paginate && config.site.posts_per_page > 0 {
+    if !config.site.generate_index {
+        log::debug!("  skip post index (site.generate_index = false)");
+    } else if config.site.paginate && config.site.posts_per_page > 0 {
         let chunks: Vec<_> = posts.chunks(config.site.posts_per_page).collect();
         let total_pages = chunks.len();
+        let page_url = |page: usize| config.route_url(&page_route(config, page));
 
         for (i, chunk) in chunks.iter().enumerate() {
             let current_page = i + 1;
-            let mut idx_ctx = tera::Context::new();
+            let mut idx_ctx = env.globals.clone();
             
             idx_ctx.insert("posts", chunk);
             idx_ctx.insert("total_pages", &total_pages);
             idx_ctx.insert("current_page", &current_page);
             idx_ctx.insert("has_prev", &(current_page > 1));
             idx_ctx.insert("has_next", &(current_page < total_pages));
-            idx_ctx.insert("data", &global_data);
+            // Absolute, base-aware URLs for `<link rel="prev|next|canonical">` and numbered pagers.
+            idx_ctx.insert("page_prev_url", &(current_page > 1).then(|| page_url(current_page - 1)));
+            idx_ctx.insert("page_next_url", &(current_page < total_pages).then(|| page_url(current_page + 1)));
+            idx_ctx.insert("page_canonical_url", &page_url(current_page));
+            idx_ctx.insert("page_first_url", &page_url(1));
+            idx_ctx.insert("page_last_url", &page_url(total_pages));
+            idx_ctx.insert("data", env.global_data);
             idx_ctx.insert("config", config);
+            idx_ctx.insert("seo", &site_seo);
+            idx_ctx.insert("lang", lang);
+            idx_ctx.insert("menus", &menus);
+            idx_ctx.insert("tag_slugs", &tag_slugs);
+            idx_ctx.insert("tag_urls", &tag_urls);
+            idx_ctx.insert("collections", collections);
+            idx_ctx.insert("stats", &stats);
+            idx_ctx.insert("page_content", &page_content.as_ref().filter(|_| current_page == 1));
             
-            let out_path = if current_page == 1 {
-                config.output_dir.join("index.html")
-            } else {
-                let page_dir = config.output_dir.join("page").join(current_page.to_string());
-                fs::create_dir_all(&page_dir)?;
-                page_dir.join("index.html")
-            };
-            
-            render_index_file(&tera, &idx_ctx, out_path, config, is_dev)?;
+            let route = page_route(config, current_page);
+            let out_path = config.output_dir.join(config.build.url_style.file(&route));
+            manifest_pages.push(PageEntry::generated("index", &route, &config.site.title, config));
+
+            render_index_file(env, &idx_ctx, &out_path, config)?;
+        }
+
+        // Page 1 lives under the pagination path, so the root just points at it.
+        if config.site.paginate_first_page && total_pages > 0 {
+            let root = config.output_dir.join(config.build.url_style.file(""));
+            env.emit(&root, redirect_html(&page_url(1)))?;
         }
     } else {
-        let mut idx_ctx = tera::Context::new();
-        idx_ctx.insert("posts", &posts);
-        idx_ctx.insert("data", &global_data);
+        let mut idx_ctx = env.globals.clone();
+        idx_ctx.insert("posts", posts);
+        idx_ctx.insert("data", env.global_data);
         idx_ctx.insert("config", config);
+        idx_ctx.insert("seo", &site_seo);
+        idx_ctx.insert("lang", lang);
+        idx_ctx.insert("menus", &menus);
+        idx_ctx.insert("tag_slugs", &tag_slugs);
+        idx_ctx.insert("tag_urls", &tag_urls);
+        idx_ctx.insert("collections", collections);
+        idx_ctx.insert("stats", &stats);
+        idx_ctx.insert("page_content", &page_content);
         
         let out_path = config.output_dir.join("index.html");
-        render_index_file(&tera, &idx_ctx, out_path, config, is_dev)?;
+        render_index_file(env, &idx_ctx, &out_path, config)?;
+        manifest_pages.push(PageEntry::generated("index", "", &config.site.title, config));
+    }
+
+    env.timings.add("index", phase);
+
+    // --- STEP 6.5: ALIAS REDIRECTS ---
+    let real_outputs: HashSet<&str> = manifest_pages.iter().map(|e| e.output.as_str()).collect();
+    for &p in posts.iter().chain(pages).chain(&standalone) {
+        let target = config.permalink_for(p);
+        for alias in &p.aliases {
+            let rel = alias_output_path(alias);
+            if real_outputs.contains(rel.to_string_lossy().as_ref()) {
+                log::warn!(
+                    "alias '{}' on '{}' collides with a generated page; skipped",
+                    alias, p.slug
+                );
+                continue;
+            }
+            env.emit_from(&p.source_path, &config.output_dir.join(rel), redirect_html(&target))?;
+        }
     }
 
     // --- STEP 7: EXTERNAL DISTRIBUTIONS ---
+    let phase = Instant::now();
     if config.site.generate_rss {
-        let rss_xml = rss::generate_rss(&posts, config);
-        fs::write(config.output_dir.join("rss.xml"), rss_xml)?;
+        let mut feed_items: Vec<&Post> = posts.iter().chain(pages).copied().filter(|p| p.in_feed(config)).collect();
+        feed_items.sort_by(|a, b| compare_posts(a, b, &config.site.sort_by));
+        let rss_xml = rss::generate_rss(&feed_items, config, env.global_data);
+        if config.site.gzip_feeds {
+            env.emit(&config.output_dir.join("rss.xml.gz"), gzip_bytes(rss_xml.as_bytes())?)?;
+            feeds.push("rss.xml.gz".to_string());
+        }
+        env.emit(&config.output_dir.join("rss.xml"), rss_xml)?;
+        feeds.push("rss.xml".to_string());
+    }
+
+    if config.site.generate_sitemap {
+        let items: Vec<&Post> = posts.iter().chain(pages).chain(&standalone).copied().collect();
+        env.emit(&config.output_dir.join("sitemap.xml"), sitemap::generate_sitemap(&items, config))?;
+        feeds.push("sitemap.xml".to_string());
     }
 
     if config.site.generate_search {
-        let search_index: Vec<serde_json::Value> = posts.iter().map(|p| {
-            let clean_text = parser::strip_markdown(&p.raw_content);
-            let snippet: String = clean_text.chars().take(140).collect();
-            json!({ 
-                "title": p.title, 
-                "slug": p.slug, 
-                "date": p.date, 
-                "tags": p.tags, 
-                "snippet": snippet 
-            })
+        let search_index: Vec<SearchEntry> = posts.iter().map(|p| {
+            let text = p.plain_text.as_str();
+            let content = match config.site.search_content_length {
+                0 => text,
+                n => &text[..text.char_indices().nth(n).map_or(text.len(), |(i, _)| i)],
+            };
+            SearchEntry {
+                title: &p.title,
+                slug: &p.slug,
+                permalink: config.permalink_for(p),
+                date: &p.date,
+                tags: &p.tags,
+                draft: p.draft,
+                snippet: parser::truncate_words(text, config.site.search_snippet_length),
+                content,
+            }
         }).collect();
-        fs::write(config.output_dir.join("search.json"), serde_json::to_string(&search_index)?)?;
+        let search_json = serde_json::to_string(&search_index)?;
+        let gzip_only = config.site.gzip_feeds && config.site.gzip_search_only;
+        if config.site.gzip_feeds {
+            env.emit(&config.output_dir.join("search.json.gz"), gzip_bytes(search_json.as_bytes())?)?;
+            feeds.push("search.json.gz".to_string());
+        }
+        if !gzip_only {
+            env.emit(&config.output_dir.join("search.json"), search_json)?;
+            feeds.push("search.json".to_string());
+        }
+
+        if config.site.generate_search_ui {
+            let (index, encoding) = if gzip_only { ("search.json.gz", "gzip") } else { ("search.json", "identity") };
+            let settings = json!({ "index": index, "encoding": encoding });
+            let script = format!("window.novosSearch = {};\n{}", settings, SEARCH_SCRIPT);
+            env.emit(&config.output_dir.join("search.js"), script)?;
+        }
+    }
+
+    if config.build.emit_manifest {
+        let manifest = Manifest::new(manifest_pages, feeds, env.start.elapsed());
+        env.emit(&config.output_dir.join("manifest.json"), serde_json::to_string_pretty(&manifest)?)?;
     }
+    env.timings.add("feeds", phase);
+
+    Ok(())
+}
 
+/// Invocation-level switches for a single build.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Building for `novos serve` (injects the live-reload script).
+    pub is_dev: bool,
+    /// Resolve and render everything, but write nothing; print the planned outputs instead.
+    pub dry_run: bool,
+    /// WebSocket route baked into the live-reload script. Empty means [DEFAULT_LIVE_RELOAD_PATH].
+    pub live_reload_path: String,
+    /// Fail the build if it logged any warning (bad dates, missing templates,
+    /// includes or assets, duplicate outputs, ...). Warnings are counted by
+    /// [crate::logger], so this has no effect unless that logger is installed.
+    pub strict: bool,
+    /// Print how long each build phase took.
+    pub timings: bool,
+    /// Shareable preview: keep drafts and future posts, and mark every page `noindex`.
+    pub preview: bool,
+    /// Set by [perform_build_incremental]: only these Markdown sources changed, so the
+    /// asset pipeline is skipped and only these items (plus listings and feeds) re-render.
+    pub changed: Option<Vec<PathBuf>>,
+}
+
+/// The orchestrator of the `novos` build.
+///
+/// `[hooks]` commands run around it: `pre_build` first (a failure aborts the build),
+/// `post_build` only once the build succeeded. Dry runs skip both.
+///
+/// With `opts.strict`, any warning logged during the build fails it (before `post_build`).
+/// Warnings are counted by [crate::logger], so strict mode needs that logger installed.
+///
+/// When `build.max_threads` is set, every parallel section (parsing, rendering,
+/// image encoding) runs inside a bounded Rayon pool instead of the global one.
+pub fn perform_build(
+    config: &Config,
+    last_run_mu: Arc<Mutex<SystemTime>>,
+    cache: &BuildCache,
+    opts: &BuildOptions,
+) -> io::Result<()> {
+    if !opts.dry_run {
+        hooks::run_hooks("pre_build", &config.hooks.pre_build)?;
+    }
+    let warnings_before = logger::warning_count();
+    run_pooled(config, last_run_mu, cache, opts)?;
+    let warnings = logger::warning_count() - warnings_before;
+    if opts.strict && warnings > 0 {
+        return Err(io::Error::other(format!("{} warning(s) treated as errors (--strict)", warnings)));
+    }
+    if !opts.dry_run {
+        hooks::run_hooks("post_build", &config.hooks.post_build)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds after the watcher saw `changed` paths.
+///
+/// Edits confined to Markdown files in `posts_dir`/`pages_dir` skip the static copy,
+/// stylesheets and images, and re-render only those items plus the index, tag pages
+/// and feeds that list them. Anything else (templates, config, data, static files,
+/// sass) or a deleted file affects every page, so it falls back to [perform_build].
+pub fn perform_build_incremental(
+    config: &Config,
+    last_run_mu: Arc<Mutex<SystemTime>>,
+    cache: &BuildCache,
+    opts: &BuildOptions,
+    changed: &[PathBuf],
+) -> io::Result<()> {
+    let content_dirs: Vec<PathBuf> = [&config.posts_dir, &config.pages_dir]
+        .into_iter()
+        .chain(config.collections.iter().map(|c| &c.dir))
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .collect();
+    let sources: Option<Vec<PathBuf>> = changed
+        .iter()
+        .map(|p| {
+            let path = fs::canonicalize(p).ok()?;
+            let is_content = path.extension().is_some_and(|ext| ext == "md")
+                && content_dirs.iter().any(|dir| path.starts_with(dir));
+            is_content.then_some(path)
+        })
+        .collect();
+
+    match sources {
+        Some(sources) if !sources.is_empty() => {
+            log::debug!("  incremental rebuild of {} file(s)", sources.len());
+            let opts = BuildOptions { changed: Some(sources), ..opts.clone() };
+            perform_build(config, last_run_mu, cache, &opts)
+        }
+        _ => perform_build(config, last_run_mu, cache, opts),
+    }
+}
+
+fn run_pooled(
+    config: &Config,
+    last_run_mu: Arc<Mutex<SystemTime>>,
+    cache: &BuildCache,
+    opts: &BuildOptions,
+) -> io::Result<()> {
+    match config.build.max_threads {
+        Some(n) if n > 0 => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .map_err(|e| io::Error::other(format!("Thread pool error: {}", e)))?;
+            pool.install(|| run_build(config, last_run_mu, cache, opts))
+        }
+        _ => run_build(config, last_run_mu, cache, opts),
+    }
+}
+
+fn run_build(
+    config: &Config,
+    last_run_mu: Arc<Mutex<SystemTime>>,
+    cache: &BuildCache,
+    opts: &BuildOptions,
+) -> io::Result<()> {
+    let BuildOptions { is_dev, dry_run, .. } = *opts;
+    let live_reload_path = match opts.live_reload_path.as_str() {
+        "" => DEFAULT_LIVE_RELOAD_PATH,
+        path => path,
+    };
+    let start = Instant::now();
+    let timings = Timings::default();
+
+    // Refuse to build into a directory that holds sources: `clean_output` would delete them.
+    if let Some(src) = config.output_clobbers_source() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "output_dir '{}' overlaps source directory '{}'; refusing to build",
+                config.output_dir.display(),
+                src.display()
+            ),
+        ));
+    }
+    // One-off builds that keep their output resume from the previous run's state,
+    // so only sources modified since are re-rendered.
+    let persist_state = !is_dev && !dry_run && !opts.preview && opts.changed.is_none() && !config.build.clean_output;
+    let mut lr = *last_run_mu.lock().unwrap();
+    let previous = (persist_state && lr == SystemTime::UNIX_EPOCH).then(BuildState::load).flatten();
+
+    let theme_dir = config.theme.as_ref().map(|t| PathBuf::from("themes").join(t));
+
+    // --- STEP 0: TEMPLATE INITIALIZATION ---
+    let mut tera = parser::init_tera("templates")?;
+    if let Some(td) = &theme_dir {
+        let theme_templates_glob = td.join("templates/**/*");
+        if let Some(glob_str) = theme_templates_glob.to_str()
+            && let Ok(theme_tera) = tera::Tera::new(glob_str)
+        {
+            // Merge theme templates into our Tera instance
+            tera.extend(&theme_tera).ok();
+        }
+    }
+
+    let zone = dates::Zone::from_config(config.site.timezone.as_deref())?;
+    tera.register_filter(
+        "date_format",
+        dates::date_format_filter(config.site.date_format.clone(), config.site.locale.clone(), zone),
+    );
+    tera.register_function("feed_links", rss::feed_links_function(config));
+
+    // Dry runs leave the output directory untouched, so the asset steps are skipped,
+    // as are incremental rebuilds where only content changed.
+    if !dry_run && opts.changed.is_none() {
+        // --- STEP 1: ASSET PIPELINE ---
+        if config.build.clean_output {
+            log::debug!("[1/5] Cleaning output directory...");
+            if config.output_dir.exists() {
+                let _ = fs::remove_dir_all(&config.output_dir);
+            }
+        }
+        
+        fs::create_dir_all(&config.output_dir)?;
+
+        // Copy static assets: Theme first, then Project (Project overrides Theme)
+        let phase = Instant::now();
+        let exclude = glob_set("build.static_exclude", &config.build.static_exclude)?;
+        if let Some(td) = &theme_dir {
+            let theme_static = td.join("static");
+            if theme_static.exists() {
+                copy_dir_all(theme_static, &config.output_dir, &exclude, config.build.follow_symlinks)?;
+            }
+        }
+        if config.static_dir.exists() {
+            copy_dir_all(&config.static_dir, &config.output_dir, &exclude, config.build.follow_symlinks)?;
+        }
+        timings.add("static copy", phase);
+
+        if config.build.convert_to_webp {
+            log::debug!("[1.5/5] Optimizing images...");
+            let phase = Instant::now();
+            process_images(config)?;
+            timings.add("images", phase);
+        }
+
+        // --- STEP 2: STYLESHEETS ---
+        log::debug!("[2/5] Compiling stylesheets...");
+        let phase = Instant::now();
+        compile_sass(config, &theme_dir, is_dev)?;
+
+        if config.build.minify_css || !config.build.browser_targets.is_empty() {
+            process_css(config)?;
+        }
+        timings.add("sass", phase);
+    }
+
+    // --- STEP 3: CONTENT INGESTION ---
+    log::debug!("[3/5] Processing content...");
+    
+    let highlighter = cache.highlighter(config)?;
+    if let Some(lang) = config.build.default_code_language.as_deref().filter(|l| !l.is_empty())
+        && config.build.use_syntect
+        && highlighter.syntax_set.find_syntax_by_token(lang).is_none()
+    {
+        log::warn!("build.default_code_language: unknown language '{}'; unlabeled code stays plain text", lang);
+    }
+
+    let global_data = load_data_dir(&theme_dir);
+
+    let phase = Instant::now();
+    let content_ignore = glob_set("build.content_ignore", &config.build.content_ignore)?;
+    let mut posts = load_markdown_dir(&config.posts_dir, &content_ignore, &zone, lr, cache)?;
+    let mut pages = load_markdown_dir(&config.pages_dir, &content_ignore, &zone, lr, cache)?;
+    // Collections feeding the index join the post list; the rest render on their own.
+    let mut collection_items: Vec<Post> = Vec::new();
+    for collection in &config.collections {
+        let mut items = load_markdown_dir(&collection.dir, &content_ignore, &zone, lr, cache)?;
+        for item in &mut items {
+            item.collection = Some(collection.name.clone());
+        }
+        if collection.in_index {
+            posts.extend(items);
+        } else {
+            collection_items.extend(items);
+        }
+    }
+    timings.add("content parse", phase);
+    for page in &mut pages {
+        page.is_page = true;
+    }
+    for item in posts.iter_mut().chain(pages.iter_mut()).chain(collection_items.iter_mut()) {
+        assign_language(item, config);
+        // Same format and locale as the `date_format` filter, so the two always agree.
+        item.date_info = dates::DateInfo::new(&item.datetime, &config.site.date_format, config.site.locale.as_deref());
+    }
+
+    if !SORT_ORDERS.contains(&config.site.sort_by.as_str()) {
+        log::warn!(
+            "unknown site.sort_by '{}', using date_desc (expected one of {})",
+            config.site.sort_by,
+            SORT_ORDERS.join(", ")
+        );
+    }
+    posts.sort_by(|a, b| compare_posts(a, b, &config.site.sort_by));
+    collection_items.sort_by(|a, b| compare_posts(a, b, &config.site.sort_by));
+
+    // Production builds drop expired content everywhere (pages, feeds, taxonomies);
+    // `serve` keeps it so authors can still preview it with the `expired` flag.
+    let mut expired: Vec<Post> = Vec::new();
+    if !is_dev {
+        // Drafts and future-dated items are unpublished: no page, feed entry or search record.
+        if !opts.preview {
+            posts.retain(Post::is_published);
+            pages.retain(Post::is_published);
+            collection_items.retain(Post::is_published);
+        }
+        for list in [&mut posts, &mut pages, &mut collection_items] {
+            let (gone, kept): (Vec<Post>, Vec<Post>) = list.drain(..).partition(|p| p.is_expired());
+            *list = kept;
+            expired.extend(gone);
+        }
+    }
+
+    // Two sources rendering to the same file would silently overwrite each other.
+    let lang_configs: HashMap<String, Config> = config.site.language_list()
+        .into_iter()
+        .map(|lang| (lang.clone(), config.for_language(&lang)))
+        .collect();
+    let mut outputs: HashMap<PathBuf, &Path> = HashMap::new();
+    for p in posts.iter().chain(pages.iter()).chain(collection_items.iter()) {
+        let dest = output_path(lang_configs.get(&p.lang).unwrap_or(config), p);
+        if let Some(previous) = outputs.insert(dest.clone(), &p.source_path) {
+            log::warn!(
+                "{} and {} both render to {}",
+                previous.display(),
+                p.source_path.display(),
+                dest.display()
+            );
+        }
+    }
+
+    let listing = cache::listing_fingerprint(
+        posts.iter().chain(pages.iter()).chain(collection_items.iter())
+            .map(|p| (p, lang_configs.get(&p.lang).unwrap_or(config).permalink_for(p))),
+    );
+    if let Some(resumed) = previous.as_ref().and_then(|state| state.resume_from(config, listing)) {
+        log::debug!("  resuming from {}", STATE_DIR);
+        lr = resumed;
+    }
+
+    let env = RenderEnv {
+        tera: &tera,
+        render: RenderOptions {
+            highlighter: config.build.use_syntect.then_some(highlighter),
+            css_classes: config.build.syntax_css_classes,
+            mermaid: config.build.mermaid,
+            emoji: config.build.emoji,
+            default_lang: config.build.default_code_language.as_deref().filter(|l| !l.is_empty()),
+        },
+        global_data: &global_data,
+        shortcodes: Shortcodes::new(config, &theme_dir),
+        translations: &build_translations(&posts, &pages, config),
+        lr,
+        changed: opts.changed.as_deref(),
+        is_dev,
+        live_reload_path,
+        dry_run,
+        preview: opts.preview,
+        globals: build_globals(config),
+        timings: &timings,
+        planned: Mutex::new(Vec::new()),
+        render_errors: Mutex::new(0),
+        unchanged: Mutex::new(0),
+        written: Mutex::new(0),
+        outputs: Mutex::new(BTreeMap::new()),
+        start,
+    };
+
+    // Validate includes up front so a missing file is reported once, before any rendering.
+    let missing_includes: usize = posts.iter().chain(pages.iter()).chain(collection_items.iter())
+        .map(|p| env.shortcodes.check_includes(&p.raw_content, &p.source_path))
+        .sum();
+    if missing_includes > 0 {
+        log::debug!("  includes {} reference(s) could not be resolved", missing_includes);
+    }
+
+    // Each language renders into its own subtree; the default language owns the root.
+    for lang in config.site.language_list() {
+        let lang_config = config.for_language(&lang);
+        let lang_posts: Vec<&Post> = posts.iter().filter(|p| p.lang == lang).collect();
+        let lang_pages: Vec<&Post> = pages.iter().filter(|p| p.lang == lang).collect();
+        // Every collection by name, including the ones merged into `posts`.
+        let lang_collections: BTreeMap<&str, Vec<&Post>> = config.collections
+            .iter()
+            .map(|c| {
+                let items = posts.iter().chain(collection_items.iter())
+                    .filter(|p| p.lang == lang && p.collection.as_deref() == Some(c.name.as_str()))
+                    .collect();
+                (c.name.as_str(), items)
+            })
+            .collect();
+        render_content(&lang_config, &lang, &lang_posts, &lang_pages, &lang_collections, &env)?;
+
+        for p in expired.iter().filter(|p| p.lang == lang) {
+            let dest = output_path(&lang_config, p);
+            if config.build.expired_stubs {
+                env.emit_from(&p.source_path, &dest, expired_stub_html(&p.title))?;
+            } else if !dry_run && dest.exists() {
+                // Remove output left over from before the post expired
+                let _ = fs::remove_file(dest);
+            }
+        }
+    }
+
+    if config.build.use_syntect && config.build.syntax_css_classes {
+        match parser::syntax_css(highlighter) {
+            Some(css) => env.emit(&config.output_dir.join("css").join("syntax.css"), css)?,
+            None => log::warn!("could not generate css/syntax.css from the syntax theme"),
+        }
+    }
+
+    // Relative to `output_dir`, so the state stays valid if the project moves.
+    let rendered: BTreeMap<PathBuf, BTreeSet<PathBuf>> = std::mem::take(&mut *env.outputs.lock().unwrap())
+        .into_iter()
+        .map(|(source, paths)| {
+            let rel = paths.iter().filter_map(|p| p.strip_prefix(&config.output_dir).ok().map(Path::to_path_buf)).collect();
+            (source, rel)
+        })
+        .collect();
+    // A page that failed to render isn't recorded; keep its old output until it renders again.
+    let render_errors = *env.render_errors.lock().unwrap();
+    if let Some(state) = previous.as_ref().filter(|_| render_errors == 0) {
+        remove_orphans(config, &theme_dir, state, &rendered);
+    }
+
+    if !dry_run && !is_dev && !config.build.precompress.is_empty() {
+        log::debug!("[+] Pre-compressing outputs...");
+        let phase = Instant::now();
+        precompress_outputs(config)?;
+        timings.add("precompress", phase);
+    }
+
+    if dry_run {
+        let mut planned = env.planned.into_inner().unwrap_or_default();
+        planned.sort();
+        for path in &planned {
+            log::info!("  would write {}", path.display());
+        }
+        log::info!("{} files would be written.", planned.len());
+
+        if opts.timings {
+            timings.report(start.elapsed());
+        }
+        let errors = env.render_errors.into_inner().unwrap_or_default();
+        if errors > 0 {
+            return Err(io::Error::other(format!("{} page(s) failed to render", errors)));
+        }
+        return Ok(());
+    }
+
+    let finished = SystemTime::now();
     if let Ok(mut lr_lock) = last_run_mu.lock() {
-        *lr_lock = SystemTime::now();
+        *lr_lock = finished;
+    }
+    if persist_state && render_errors == 0
+        && let Err(e) = BuildState::save(config, listing, finished, rendered)
+    {
+        log::warn!("could not save {}: {}", STATE_DIR, e);
     }
     
-    if verbose {
-        println!("\x1b[36msuccess\x1b[0m build complete in {:.2}s.", start.elapsed().as_secs_f32());
+    let unchanged = env.unchanged.into_inner().unwrap_or_default();
+    let written = env.written.into_inner().unwrap_or_default();
+    log::debug!("  outputs {} unchanged, {} written", unchanged, written);
+
+    // Local images/scripts/media that the static copy didn't provide.
+    let phase = Instant::now();
+    let missing = links::check_assets(config);
+    for asset in &missing {
+        log::warn!("{}: missing asset {}", asset.page.display(), asset.target);
     }
+    timings.add("asset check", phase);
+
+    if opts.timings {
+        timings.report(start.elapsed());
+    }
+    log::debug!("  built in {:.2}s", start.elapsed().as_secs_f32());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str("").unwrap()
+    }
+
+    fn post(raw: &str) -> Post {
+        parser::parse_frontmatter(raw, "post", SystemTime::now(), &dates::Zone::from_config(None).unwrap())
+    }
+
+    #[test]
+    fn webp_rewrite_only_touches_local_images() {
+        let base = "https://example.com";
+        let cdn = r#"<img src="https://cdn.example.net/a.png">"#;
+        assert_eq!(rewrite_to_webp(cdn.to_string(), base), cdn);
+        assert_eq!(rewrite_to_webp(r#"<img src="/img/a.png">"#.to_string(), base), r#"<img src="/img/a.webp">"#);
+        assert_eq!(
+            rewrite_to_webp(r#"<img src="https://example.com/img/a.JPG">"#.to_string(), base),
+            r#"<img src="https://example.com/img/a.webp">"#
+        );
+        assert!(is_local_asset("img/a.", base));
+        assert!(!is_local_asset("https://example.com.evil.net/a.", base));
+    }
+
+    #[test]
+    fn webp_mime_rewrite_is_scoped_to_rewritten_tags() {
+        let html = concat!(
+            r#"<script type="application/ld+json">{"image":{"encodingFormat":"image/png","url":"https://cdn.other/x.png"}}</script>"#,
+            r#"<meta property="og:image:type" content="image/png">"#,
+            r#"<picture><source srcset="/a.png" type="image/png"><img src="/a.png"></picture>"#,
+        );
+        let out = rewrite_to_webp(html.to_string(), "https://example.com");
+        assert!(out.contains(r#"<source srcset="/a.webp" type="image/webp">"#), "{}", out);
+        assert!(out.contains(r#""encodingFormat":"image/png""#), "{}", out);
+        assert!(out.contains(r#"content="image/png""#), "{}", out);
+    }
+
+    #[test]
+    fn minified_json_ld_stays_valid_json() {
+        let html = "<html><head><script type=\"application/ld+json\">\n{\n  \"@context\": \"https://schema.org\",\n  \"name\": \"A  spaced   name\",\n  \"description\": \"line\\nbreak\"\n}\n</script></head><body><p>x</p></body></html>";
+        let out = process_html(html.to_string(), &config(), None, true);
+
+        let body_re = Regex::new(r#"(?s)<script type="?application/ld\+json"?>(.*?)</script>"#).unwrap();
+        let body = &body_re.captures(&out).expect("JSON-LD block kept")[1];
+        let json: Value = serde_json::from_str(body).expect("valid JSON after minification");
+        assert_eq!(json["name"], "A  spaced   name");
+        assert_eq!(json["description"], "line\nbreak");
+    }
+
+    #[test]
+    fn minify_false_keeps_whitespace() {
+        let html = "<html><body>\n  <p>x    y</p>\n</body></html>";
+        let p = post("---\ntitle: Raw\nminify: false\n---\nBody\n");
+        let mut config = config();
+        config.build.minify_html = true;
+
+        assert!(!p.minify(&config));
+        assert_eq!(process_html(html.to_string(), &config, None, p.minify(&config)), html);
+        assert!(!process_html(html.to_string(), &config, None, true).contains("x    y"));
+    }
+
+    #[test]
+    fn feeds_and_sitemap_are_never_minified() {
+        let p = post("---\ntitle: Spaced    title\ndate: 2024-01-01\n---\nBody\n");
+        let mut config = config();
+        config.build.minify_html = true;
+
+        let sitemap = sitemap::generate_sitemap(&[&p], &config);
+        assert!(sitemap.contains("\n  <url>"), "{}", sitemap);
+        config.build.minify_html = false;
+        assert_eq!(sitemap::generate_sitemap(&[&p], &config), sitemap);
+
+        config.build.minify_html = true;
+        let feed = rss::generate_rss(&[&p], &config, &Value::Null);
+        assert!(feed.contains("Spaced    title"), "{}", feed);
+    }
+
+    #[test]
+    fn listing_fingerprint_ignores_body_edits() {
+        let listing = |raw: &str| cache::listing_fingerprint([(&post(raw), "/post.html".to_string())]);
+        let original = listing("---\ntitle: A\ndate: 2024-01-01\nexcerpt: Short.\n---\nBody.");
+        assert_eq!(original, listing("---\ntitle: A\ndate: 2024-01-01\nexcerpt: Short.\n---\nA longer body."));
+        assert_ne!(original, listing("---\ntitle: B\ndate: 2024-01-01\nexcerpt: Short.\n---\nBody."));
+    }
+}
@@ -0,0 +1,227 @@
+//! State that survives between builds.
+//!
+//! A one-off `novos build` uses a fresh [BuildCache]; `novos serve` keeps one
+//! alive for the whole session so rebuilds skip work that cannot have changed.
+//! Between separate invocations only [BuildState] survives, on disk.
+
+use crate::{config::Config, models::Post};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs, io,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Instant, SystemTime},
+};
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+
+/// Loaded syntect assets. Expensive to build, immutable once loaded.
+pub struct Highlighter {
+    pub syntax_set: SyntaxSet,
+    pub theme: Theme,
+    /// Per-language overrides from `[build.syntax_themes]`, keyed by lowercase token.
+    pub lang_themes: HashMap<String, Theme>,
+}
+
+impl Highlighter {
+    /// Loads the default syntax set and the configured theme.
+    fn load(config: &Config) -> io::Result<Self> {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+
+        // Custom Syntax Theme Logic: 
+        // If syntax_theme_path is set, attempt to load that file.
+        // Otherwise, fallback to the internal ThemeSet.
+        let theme: Theme = if let Some(ref path_str) = config.build.syntax_theme_path {
+            let theme_path = Path::new(path_str);
+            if theme_path.exists() {
+                let mut reader = io::BufReader::new(fs::File::open(theme_path)?);
+                ThemeSet::load_from_reader(&mut reader)
+                    .map_err(|e| io::Error::other(format!("Failed to load custom theme: {}", e)))?
+            } else {
+                // Path provided but not found: Fallback to Ocean Dark
+                let ts = ThemeSet::load_defaults();
+                ts.themes.get("base16-ocean.dark").unwrap().clone()
+            }
+        } else {
+            // No path provided: Load from standard syntect defaults
+            let ts = ThemeSet::load_defaults();
+            ts.themes.get(&config.build.syntax_theme)
+                .cloned()
+                .unwrap_or_else(|| ts.themes.get("base16-ocean.dark").unwrap().clone())
+        };
+
+        // Validate overrides up front so a typo fails the build instead of silently
+        // falling back on every code block.
+        let mut lang_themes = HashMap::new();
+        if !config.build.syntax_themes.is_empty() {
+            let ts = ThemeSet::load_defaults();
+            for (lang, name) in &config.build.syntax_themes {
+                let theme = ts.themes.get(name).cloned().ok_or_else(|| {
+                    let mut known: Vec<&String> = ts.themes.keys().collect();
+                    known.sort();
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "build.syntax_themes: unknown theme '{}' for '{}' (available: {})",
+                            name,
+                            lang,
+                            known.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+                        ),
+                    )
+                })?;
+                lang_themes.insert(lang.to_lowercase(), theme);
+            }
+        }
+
+        Ok(Highlighter { syntax_set, theme, lang_themes })
+    }
+}
+
+/// Project directory holding state persisted between `novos build` runs.
+pub const STATE_DIR: &str = ".novos-cache";
+
+/// What the previous successful build saw, so the next `novos build` (with
+/// `clean_output = false`) only re-renders sources modified since, and can remove
+/// outputs whose sources are gone.
+#[derive(Serialize, Deserialize)]
+pub struct BuildState {
+    /// novos version that wrote the state; upgrading invalidates it.
+    version: String,
+    /// Hash of the config and every template, theme, data and include file.
+    fingerprint: u64,
+    /// [listing_fingerprint] of the published items.
+    listing: u64,
+    last_run: SystemTime,
+    /// Every file rendered, relative to `output_dir`, keyed by the source it came from.
+    /// Pages without a source (indices, tags, feeds) are keyed by the empty path.
+    outputs: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl BuildState {
+    fn path() -> PathBuf {
+        Path::new(STATE_DIR).join("state.json")
+    }
+
+    /// The state left by the previous build, if any.
+    pub fn load() -> Option<Self> {
+        serde_json::from_str(&fs::read_to_string(Self::path()).ok()?).ok()
+    }
+
+    /// When the previous build finished, if config, templates and every item's
+    /// listed fields are unchanged since. Otherwise every page has to be re-rendered,
+    /// since any of them may list the other items.
+    pub fn resume_from(&self, config: &Config, listing: u64) -> Option<SystemTime> {
+        let valid = self.version == env!("CARGO_PKG_VERSION")
+            && self.fingerprint == fingerprint(config)
+            && self.listing == listing;
+        valid.then_some(self.last_run)
+    }
+
+    /// Outputs the previous build rendered that `outputs` no longer contains.
+    pub fn orphans<'a>(&'a self, outputs: &'a BTreeMap<PathBuf, BTreeSet<PathBuf>>) -> impl Iterator<Item = &'a PathBuf> {
+        let current: BTreeSet<&PathBuf> = outputs.values().flatten().collect();
+        self.outputs.values().flatten().filter(move |p| !current.contains(p))
+    }
+
+    /// Records a successful build that finished at `last_run` and rendered `outputs`.
+    pub fn save(
+        config: &Config,
+        listing: u64,
+        last_run: SystemTime,
+        outputs: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    ) -> io::Result<()> {
+        let state = BuildState {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            fingerprint: fingerprint(config),
+            listing,
+            last_run,
+            outputs,
+        };
+        fs::create_dir_all(STATE_DIR)?;
+        fs::write(Self::path(), serde_json::to_string_pretty(&state)?)
+    }
+}
+
+/// Hash of everything a listing can show about each item: its serialized fields and
+/// permalink, minus the body, the counts derived from it and `updated` (which falls
+/// back to the file's mtime). Adding, removing or retitling an item changes it;
+/// editing only a body doesn't.
+pub fn listing_fingerprint<'a>(items: impl IntoIterator<Item = (&'a Post, String)>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (post, permalink) in items {
+        let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(post) else {
+            continue;
+        };
+        for body in ["raw_content", "raw_frontmatter", "word_count", "reading_time", "updated", "mtime"] {
+            fields.remove(body);
+        }
+        serde_json::Value::Object(fields).to_string().hash(&mut hasher);
+        permalink.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Changes whenever anything that shapes every page does: the config itself, or the
+/// size or mtime of a file under the template, theme, data or includes directories.
+fn fingerprint(config: &Config) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(config).unwrap_or_default().hash(&mut hasher);
+
+    let theme_dir = config.theme.as_ref().map(|t| PathBuf::from("themes").join(t));
+    let dirs = [Some(PathBuf::from("templates")), Some(PathBuf::from("data")), Some(config.includes_dir.clone()), theme_dir];
+    for dir in dirs.into_iter().flatten() {
+        let files = walkdir::WalkDir::new(&dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+        for entry in files {
+            entry.path().hash(&mut hasher);
+            if let Ok(meta) = entry.metadata() {
+                meta.len().hash(&mut hasher);
+                meta.modified().ok().hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Cross-build cache, shared by reference with every `perform_build` call.
+#[derive(Default)]
+pub struct BuildCache {
+    highlighter: OnceLock<Highlighter>,
+    /// Parsed Markdown keyed by source path, tagged with the mtime it was read at.
+    posts: Mutex<HashMap<PathBuf, (SystemTime, Post)>>,
+}
+
+impl BuildCache {
+    /// Returns the syntect assets, loading them on first use.
+    pub fn highlighter(&self, config: &Config) -> io::Result<&Highlighter> {
+        if let Some(h) = self.highlighter.get() {
+            log::debug!("  reused cached syntax assets");
+            return Ok(h);
+        }
+
+        let start = Instant::now();
+        let loaded = Highlighter::load(config)?;
+        log::debug!("  loaded syntax assets in {}ms", start.elapsed().as_millis());
+        Ok(self.highlighter.get_or_init(|| loaded))
+    }
+
+    /// Returns a previously parsed post if `path` hasn't been modified since.
+    pub fn cached_post(&self, path: &Path, mtime: SystemTime) -> Option<Post> {
+        let posts = self.posts.lock().ok()?;
+        posts.get(path)
+            .filter(|(cached_mtime, _)| *cached_mtime >= mtime)
+            .map(|(_, post)| post.clone())
+    }
+
+    /// Remembers a freshly parsed post.
+    pub fn store_post(&self, path: PathBuf, mtime: SystemTime, post: &Post) {
+        if let Ok(mut posts) = self.posts.lock() {
+            posts.insert(path, (mtime, post.clone()));
+        }
+    }
+}
@@ -4,8 +4,11 @@
 //! sane defaults for any missing fields. It is structured into sub-modules
 //! (Site, Build, and Social) to keep the configuration file organized.
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use crate::models::Post;
 
 /// The root configuration schema for a novos project.
 ///
@@ -79,6 +82,153 @@ pub struct Config {
     /// Settings that control the behavior of the build engine (Sass, Minification, etc.).
     #[serde(default)]
     pub build: BuildSettings,
+
+    /// Shell commands run before and after each build.
+    #[serde(default)]
+    pub hooks: HooksSettings,
+
+    /// Where `novos deploy` publishes the site.
+    #[serde(default)]
+    pub deploy: DeploySettings,
+
+    /// `novos serve` defaults; command-line flags override them.
+    #[serde(default)]
+    pub server: ServerSettings,
+
+    /// iTunes podcast metadata. When present, the RSS feed carries the iTunes namespace.
+    pub podcast: Option<PodcastSettings>,
+
+    /// Content types beyond posts and pages (`[[collections]]`), e.g. projects or talks.
+    #[serde(default)]
+    pub collections: Vec<CollectionSettings>,
+
+    /// Language sub-path (e.g. "es") for content URLs when rendering a non-default
+    /// language. Set by [Config::for_language]; never read from `novos.toml`.
+    #[serde(skip)]
+    pub lang_prefix: String,
+}
+
+impl Config {
+    /// Resolves a site-relative path into an absolute URL, honoring `base`.
+    /// Already-absolute URLs are returned unchanged.
+    pub fn absolute_url(&self, path: &str) -> String {
+        if path.contains("://") || path.starts_with("//") {
+            return path.to_string();
+        }
+
+        let mut url = self.base_url.trim_end_matches('/').to_string();
+        let base = self.base.trim_matches('/');
+        if !base.is_empty() {
+            url.push('/');
+            url.push_str(base);
+        }
+        url.push('/');
+        url.push_str(path.trim_start_matches('/'));
+        url
+    }
+
+    /// Like [Config::absolute_url], but for rendered content that lives inside the
+    /// current language subtree. Shared assets should use `absolute_url` instead.
+    pub fn content_url(&self, path: &str) -> String {
+        if self.lang_prefix.is_empty() {
+            self.absolute_url(path)
+        } else {
+            self.absolute_url(&format!("{}/{}", self.lang_prefix, path.trim_start_matches('/')))
+        }
+    }
+
+    /// Language code of the subtree being rendered.
+    pub fn language(&self) -> &str {
+        if self.lang_prefix.is_empty() { &self.site.default_language } else { &self.lang_prefix }
+    }
+
+    /// Directories the build reads from, in the order they are checked for overlaps.
+    /// Includes the project root itself, so `output_dir = "."` is caught.
+    pub fn source_dirs(&self) -> Vec<&Path> {
+        [
+            self.posts_dir.as_path(),
+            self.pages_dir.as_path(),
+            self.static_dir.as_path(),
+            self.includes_dir.as_path(),
+        ]
+        .into_iter()
+        .chain(self.collections.iter().map(|c| c.dir.as_path()))
+        .chain([
+            Path::new("templates"),
+            Path::new("sass"),
+            Path::new("data"),
+            Path::new("."),
+        ])
+        .collect()
+    }
+
+    /// The `[[collections]]` entry called `name`.
+    pub fn collection(&self, name: &str) -> Option<&CollectionSettings> {
+        self.collections.iter().find(|c| c.name == name)
+    }
+
+    /// Returns the first source directory that lives at or beneath `output_dir`,
+    /// i.e. one that cleaning or writing the output would destroy.
+    pub fn output_clobbers_source(&self) -> Option<&Path> {
+        let out = normalize_path(&self.output_dir);
+        self.source_dirs()
+            .into_iter()
+            .find(|src| normalize_path(src).starts_with(&out))
+    }
+
+    /// A copy of this config scoped to one language's output subtree.
+    /// The default language is returned unchanged.
+    pub fn for_language(&self, lang: &str) -> Config {
+        let mut scoped = self.clone();
+        if lang != self.site.default_language {
+            scoped.output_dir = self.output_dir.join(lang);
+            scoped.lang_prefix = lang.to_string();
+        }
+        scoped
+    }
+
+    /// Route (output path without extension) of a post or page.
+    pub fn route_for(&self, post: &Post) -> String {
+        let outdir = match post.collection.as_deref().and_then(|name| self.collection(name)) {
+            Some(collection) => collection.output_dir(),
+            None if post.is_page => "",
+            None => self.posts_outdir.trim_matches('/'),
+        };
+        let subdir = if self.build.mirror_source_dirs { post.subdir.as_str() } else { "" };
+        [outdir, subdir, post.slug.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Where a rendered post or page is written, relative to `output_dir`.
+    pub fn output_file_for(&self, post: &Post) -> String {
+        self.build.url_style.file(&self.route_for(post))
+    }
+
+    /// Public URL of a route in the current language, per `build.url_style`.
+    pub fn route_url(&self, route: &str) -> String {
+        self.content_url(&self.build.url_style.url(route))
+    }
+
+    /// Public URL of a rendered post or page.
+    pub fn permalink_for(&self, post: &Post) -> String {
+        self.route_url(&self.route_for(post))
+    }
+}
+
+impl SiteMetadata {
+    /// Every language to build, default language first.
+    pub fn language_list(&self) -> Vec<String> {
+        let mut langs = vec![self.default_language.clone()];
+        for lang in &self.languages {
+            if !langs.contains(lang) {
+                langs.push(lang.clone());
+            }
+        }
+        langs
+    }
 }
 
 /// Metadata describing the website for SEO and RSS purposes.
@@ -96,29 +246,308 @@ pub struct SiteMetadata {
     #[serde(default)]
     pub author: String,
 
+    /// strftime-style format used by the `date_format` template filter and `date_info.display`.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+
+    /// Locale for month/day names in formatted dates (e.g. "es_ES").
+    pub locale: Option<String>,
+
+    /// Timezone for frontmatter dates without an offset, as an IANA name
+    /// ("America/New_York") or a fixed offset ("+02:00"). Defaults to UTC.
+    pub timezone: Option<String>,
+
+    /// Image used for social previews when a post has no `cover_image`.
+    /// May be absolute or relative to the site root.
+    pub default_image: Option<String>,
+
     /// Whether to generate an `rss.xml` file in the output directory.
     #[serde(default = "default_bool_true")]
     pub generate_rss: bool,
 
+    /// Newest items written to `rss.xml`; `0` includes everything. Independent of
+    /// `posts_per_page`, which only sizes the homepage index.
+    #[serde(default = "default_feed_item_count")]
+    pub feed_item_count: usize,
+
     /// Whether to generate a `search.json` index for client-side search logic.
     #[serde(default = "default_bool_true")]
     pub generate_search: bool,
 
+    /// Emit a dependency-free `search.js` that searches `search.json` client-side.
+    /// Templates need an `#novos-search-input` field and a `#novos-search-results` container.
+    #[serde(default = "default_bool_false")]
+    pub generate_search_ui: bool,
+
+    /// Emit a `tags.json` listing every tag with its post count and post slugs,
+    /// for external tag-browsing widgets and archive tooling.
+    #[serde(default = "default_bool_false")]
+    pub generate_taxonomy_index: bool,
+
+    /// Target length (in characters) of the `snippet` preview in `search.json`.
+    /// Cut on a word boundary with a trailing ellipsis.
+    #[serde(default = "default_search_snippet_length")]
+    pub search_snippet_length: usize,
+
+    /// Maximum characters of body text stored per post in `search.json`.
+    /// `0` stores the full text.
+    #[serde(default = "default_search_content_length")]
+    pub search_content_length: usize,
+
+    /// Also write `search.json.gz` and `rss.xml.gz` next to the plain files, for hosts
+    /// that don't compress responses themselves.
+    #[serde(default = "default_bool_false")]
+    pub gzip_feeds: bool,
+
+    /// With `gzip_feeds`, write only `search.json.gz` and skip the plain `search.json`.
+    /// The built-in `search.js` then fetches it and inflates it, unless the host already
+    /// served it with `Content-Encoding: gzip`.
+    #[serde(default = "default_bool_false")]
+    pub gzip_search_only: bool,
+
+    /// Whether to generate a `sitemap.xml` file in the output directory.
+    #[serde(default = "default_bool_true")]
+    pub generate_sitemap: bool,
+
+    /// Include pages (from `pages_dir`) in the RSS feed. Overridable per item via `in_feed`.
+    #[serde(default = "default_bool_false")]
+    pub pages_in_feed: bool,
+
+    /// Include pages in `sitemap.xml`. Overridable per item via `in_sitemap`.
+    #[serde(default = "default_bool_true")]
+    pub pages_in_sitemap: bool,
+
+    // --- Languages ---
+
+    /// Additional language codes (e.g. `["en", "es"]`). Content named `slug.<lang>.md`
+    /// is built under `/<lang>/`; the default language builds at the root.
+    #[serde(default)]
+    pub languages: Vec<String>,
+
+    /// Language for content without a suffix. Rendered at the site root and
+    /// advertised as the feed `<language>`. Also accepted as `language`.
+    #[serde(default = "default_language", alias = "language")]
+    pub default_language: String,
+
+    /// Order of the post list used by the index, pagination and feeds:
+    /// `"date_desc"` (default), `"date_asc"`, `"title"` or `"weight"`.
+    /// Weight ties are broken by date, newest first.
+    #[serde(default = "default_sort_by")]
+    pub sort_by: String,
+
     // --- Pagination ---
 
+    /// Render the automatic post index (the site root and `<pagination_path>/N`). Turn off for
+    /// page-only sites whose homepage comes from a static file or page.
+    #[serde(default = "default_bool_true")]
+    pub generate_index: bool,
+
     /// Toggle to enable or disable pagination for the main post list.
     #[serde(default = "default_bool_false")]
     pub paginate: bool,
 
-    /// Number of posts to show per page if pagination is enabled.
+    /// Number of posts to show per index page if pagination is enabled.
+    /// Feed length is set separately by `feed_item_count`.
     #[serde(default = "default_posts_per_page")]
     pub posts_per_page: usize,
+
+    /// Directory holding pages 2..N of the post index (`page` -> `page/2`).
+    #[serde(default = "default_pagination_path")]
+    pub pagination_path: String,
+
+    /// Put page 1 at `<pagination_path>/1` too, leaving a redirect at the site root.
+    #[serde(default)]
+    pub paginate_first_page: bool,
+}
+
+/// Output layout selected by `build.url_style`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlStyle {
+    #[default]
+    Html,
+    Pretty,
+    Extensionless,
+}
+
+impl UrlStyle {
+    /// File for a route (an extensionless path like `tags/rust`), relative to the output directory.
+    /// The empty route is the site index.
+    pub fn file(self, route: &str) -> String {
+        let route = route.trim_matches('/');
+        match self {
+            _ if route.is_empty() => "index.html".to_string(),
+            UrlStyle::Pretty => format!("{}/index.html", route),
+            UrlStyle::Html | UrlStyle::Extensionless => format!("{}.html", route),
+        }
+    }
+
+    /// Site-relative URL path that serves [UrlStyle::file] for the same route.
+    pub fn url(self, route: &str) -> String {
+        let route = route.trim_matches('/');
+        match self {
+            _ if route.is_empty() => String::new(),
+            UrlStyle::Html => format!("{}.html", route),
+            UrlStyle::Pretty => format!("{}/", route),
+            UrlStyle::Extensionless => route.to_string(),
+        }
+    }
+}
+
+/// Lifecycle commands (`[hooks]`), run through the system shell from the project root.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct HooksSettings {
+    /// Run in order before every build (including each `serve` rebuild).
+    /// A non-zero exit aborts the build.
+    #[serde(default)]
+    pub pre_build: Vec<String>,
+
+    /// Run in order after a build succeeds.
+    #[serde(default)]
+    pub post_build: Vec<String>,
+}
+
+/// `[deploy]` options. Only the fields for the chosen `target` are read.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DeploySettings {
+    /// `"git"` (push `output_dir` to a branch) or `"rsync"`.
+    pub target: Option<String>,
+
+    /// git: branch that receives the built site.
+    #[serde(default = "default_deploy_branch")]
+    pub branch: String,
+
+    /// git: remote of the project repository to push to.
+    #[serde(default = "default_deploy_remote")]
+    pub remote: String,
+
+    /// git: commit message for the deploy commit.
+    #[serde(default = "default_deploy_message")]
+    pub message: String,
+
+    /// git: force-push a single fresh commit, replacing the branch's history on every
+    /// deploy. `false` commits on top of the branch and pushes normally.
+    #[serde(default = "default_bool_true")]
+    pub force: bool,
+
+    /// rsync: destination host, e.g. `user@example.com`.
+    pub host: Option<String>,
+
+    /// rsync: destination directory on the host.
+    pub path: Option<String>,
+
+    /// rsync: remove remote files that no longer exist in the build.
+    #[serde(default = "default_bool_false")]
+    pub delete: bool,
+}
+
+/// One `[[collections]]` entry: a directory of Markdown rendered like posts, under its
+/// own output directory and template.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CollectionSettings {
+    /// Identifies the collection in templates (`collections.<name>`); also the default
+    /// output directory and template name.
+    pub name: String,
+
+    /// Directory holding the collection's Markdown files.
+    pub dir: PathBuf,
+
+    /// Output sub-directory; defaults to `name`. `""` renders items at the site root.
+    pub output: Option<String>,
+
+    /// Template for items; defaults to `<name>.html` when the theme has one, else `post.html`.
+    pub template: Option<String>,
+
+    /// Merge items into the main post list (index, pagination, tags, feeds and search).
+    /// Otherwise the collection stands alone, with tag pages under `<output>/tags/`.
+    #[serde(default = "default_bool_false")]
+    pub in_index: bool,
+}
+
+impl CollectionSettings {
+    /// Route prefix of the collection's items, without surrounding slashes.
+    pub fn output_dir(&self) -> &str {
+        self.output.as_deref().unwrap_or(&self.name).trim_matches('/')
+    }
+}
+
+/// `[server]` options for `novos serve`. Command-line flags take precedence.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ServerSettings {
+    /// Port to listen on (`--port`).
+    #[serde(default = "default_server_port")]
+    pub port: u16,
+
+    /// Address to bind (`--host`). `"127.0.0.1"` keeps the server off the local network.
+    #[serde(default = "default_server_host")]
+    pub host: String,
+
+    /// Open the site in the default browser once the server is up (`--open`).
+    #[serde(default = "default_bool_false")]
+    pub open: bool,
+
+    /// How often the file watcher polls for changes, in milliseconds.
+    /// Also used by `novos build --watch`.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+
+    /// WebSocket route used for live-reload (`--live-path`).
+    #[serde(default = "default_live_reload_path")]
+    pub live_reload_path: String,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            port: default_server_port(),
+            host: default_server_host(),
+            open: false,
+            poll_interval: default_poll_interval(),
+            live_reload_path: default_live_reload_path(),
+        }
+    }
+}
+
+impl Default for DeploySettings {
+    fn default() -> Self {
+        Self {
+            target: None,
+            branch: default_deploy_branch(),
+            remote: default_deploy_remote(),
+            message: default_deploy_message(),
+            force: true,
+            host: None,
+            path: None,
+            delete: false,
+        }
+    }
+}
+
+/// Channel-level iTunes fields for podcast feeds.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PodcastSettings {
+    /// Podcast author; defaults to `site.author`.
+    pub author: Option<String>,
+
+    /// Whether the show contains explicit content.
+    #[serde(default = "default_bool_false")]
+    pub explicit: bool,
+
+    /// iTunes category (e.g. "Technology").
+    pub category: Option<String>,
+
+    /// Cover art URL, absolute or relative to the site root.
+    pub image: Option<String>,
+
+    /// Longer show description for podcast directories.
+    pub summary: Option<String>,
 }
 
 /// Flags and options that tune the build process.
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct BuildSettings {
     /// If true, the output directory is wiped before every build to ensure a clean state.
+    /// Turn off to keep unchanged outputs (and their mtimes) in place between builds.
     #[serde(default = "default_bool_true")]
     pub clean_output: bool,
 
@@ -127,10 +556,24 @@ pub struct BuildSettings {
     #[serde(default = "default_sass_style")]
     pub sass_style: String,
 
+    /// Emit debug-friendly stylesheets (expanded, annotated with their source file)
+    /// even for production builds. Always active under `novos serve`.
+    #[serde(default = "default_bool_false")]
+    pub sass_source_map: bool,
+
     /// Attempt to minify the final HTML output to save bandwidth.
     #[serde(default = "default_bool_false")]
     pub minify_html: bool,
 
+    /// Minify every `.css` file in the output (compiled Sass and copied static CSS).
+    #[serde(default = "default_bool_false")]
+    pub minify_css: bool,
+
+    /// Browserslist queries (e.g. `["> 0.5%", "last 2 versions"]`) used to add
+    /// vendor prefixes to output CSS. Empty disables autoprefixing.
+    #[serde(default)]
+    pub browser_targets: Vec<String>,
+
     /// Toggle for syntax highlighting in code blocks via Syntect.
     #[serde(default = "default_bool_true")]
     pub use_syntect: bool,
@@ -139,15 +582,168 @@ pub struct BuildSettings {
     #[serde(default = "default_theme")]
     pub syntax_theme: String,
 
+    /// Per-language theme overrides, e.g. `{ bash = "base16-ocean.dark", rust = "InspiredGitHub" }`.
+    /// Keys are code-fence info tokens; unmapped languages use `syntax_theme`.
+    #[serde(default)]
+    pub syntax_themes: HashMap<String, String>,
+
+    /// Highlight with CSS classes instead of inline styles, and write the theme's
+    /// colors to `css/syntax.css` for templates to link. `syntax_themes` only
+    /// applies to inline styles.
+    #[serde(default)]
+    pub syntax_css_classes: bool,
+
+    /// Replace `:shortcode:` emoji names (`:rocket:`, `:tada:`) in text with the emoji.
+    /// Code spans and blocks are left alone, as are unknown names.
+    #[serde(default)]
+    pub emoji: bool,
+
+    /// Syntax used to highlight fenced code blocks that have no language, e.g. `"rust"`.
+    /// Unset (or an unknown name) leaves them as plain text.
+    #[serde(default)]
+    pub default_code_language: Option<String>,
+
+    /// Literal text substitutions applied to Markdown bodies before rendering, in the
+    /// order written (e.g. `"{{VERSION}}" = "2.1.0"`). Fenced code blocks are left alone.
+    #[serde(default)]
+    pub replacements: IndexMap<String, String>,
+
+    /// Render ```` ```mermaid ```` blocks as `<pre class="mermaid">` diagrams instead of code.
+    #[serde(default)]
+    pub mermaid: bool,
+
+    /// Module URL of mermaid.js, added once to each page with a diagram. Defaults
+    /// to the jsDelivr build; set to `""` to load mermaid from your own templates.
+    pub mermaid_script: Option<String>,
+
     /// Path to a custom `.tmTheme` file if a built-in theme isn't used.
     pub syntax_theme_path: Option<PathBuf>,
 
+    /// Glob patterns for files in `static_dir` (and the theme's `static/`) that are
+    /// not copied to the output, e.g. `["*.psd", ".DS_Store", "originals/**"]`.
+    /// Paths are relative to each static root.
+    #[serde(default)]
+    pub static_exclude: Vec<String>,
+
+    /// Keep the folder layout of `posts_dir`/`pages_dir` in output paths and URLs
+    /// (`posts/2024/hello.md` -> `2024/hello.html`). Off by default: nested files
+    /// are still found, but publish flat under their file name.
+    #[serde(default)]
+    pub mirror_source_dirs: bool,
+
+    /// Glob patterns for Markdown files in `posts_dir` and `pages_dir` that are not
+    /// published, such as scaffolding. Defaults to `README.md` and names starting
+    /// with `_` or `.` (`_index.md` is always kept for the homepage).
+    #[serde(default = "default_content_ignore")]
+    pub content_ignore: Vec<String>,
+
+    /// Copy the contents behind symlinks in static directories instead of
+    /// recreating the links themselves in the output.
+    #[serde(default = "default_bool_false")]
+    pub follow_symlinks: bool,
+
     /// If enabled, the engine will attempt to convert source images to WebP format.
     #[serde(default = "default_bool_false")]
     pub convert_to_webp: bool,
 
+    /// Prefix root-relative `href`/`src` links in generated HTML with `base`,
+    /// so `/about` becomes `/blog/about` when hosting under a sub-path.
+    #[serde(default = "default_bool_false")]
+    pub rewrite_base_links: bool,
+
+    /// Pre-compressed variants to write next to text outputs: `"gzip"` (`.gz`)
+    /// and/or `"brotli"` (`.br`). Empty disables pre-compression.
+    #[serde(default)]
+    pub precompress: Vec<String>,
+
+    /// Leave a small "no longer available" page (marked `noindex`) where an
+    /// expired post used to be, instead of removing it outright.
+    #[serde(default = "default_bool_false")]
+    pub expired_stubs: bool,
+
+    /// Upper bound on worker threads for parallel build steps. Unset uses one per core.
+    pub max_threads: Option<usize>,
+
+    /// Write a `manifest.json` describing every generated page and feed.
+    #[serde(default = "default_bool_false")]
+    pub emit_manifest: bool,
+
+    /// How tag names become file names and URLs: `"unicode"` (keep letters from
+    /// any script), `"ascii"` (transliterate) or `"passthrough"` (keep as typed,
+    /// minus unsafe characters).
+    #[serde(default = "default_slugify_mode")]
+    pub slugify_mode: String,
+
+    /// How pages are laid out on disk and linked: `"html"` (`about.html`),
+    /// `"pretty"` (`about/index.html`, linked as `about/`) or `"extensionless"`
+    /// (`about.html`, linked as `about`, for hosts that resolve the extension).
+    /// Applies to posts, pages, tag pages and pagination alike.
+    #[serde(default)]
+    pub url_style: UrlStyle,
+
     /// Optional directory containing custom `.sublime-syntax` files for additional language support.
     pub custom_syntax_dir: Option<PathBuf>,
+
+    /// CommonMark extensions enabled when rendering Markdown (`[build.markdown]`).
+    #[serde(default)]
+    pub markdown: MarkdownSettings,
+}
+
+/// Toggles for pulldown-cmark extensions. Everything is on unless disabled.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MarkdownSettings {
+    /// GitHub-style pipe tables.
+    #[serde(default = "default_bool_true")]
+    pub tables: bool,
+
+    /// `[^1]` footnote references and definitions.
+    #[serde(default = "default_bool_true")]
+    pub footnotes: bool,
+
+    /// `~~struck~~` text.
+    #[serde(default = "default_bool_true")]
+    pub strikethrough: bool,
+
+    /// `- [ ]` / `- [x]` task list items.
+    #[serde(default = "default_bool_true")]
+    pub tasklists: bool,
+
+    /// Curly quotes, en/em dashes and ellipses in prose (`"hello"`, `--flag`).
+    /// Inline code and code blocks are never transformed.
+    #[serde(default = "default_bool_true")]
+    pub smart_punctuation: bool,
+
+    /// `# Heading {#id .class}` attribute blocks.
+    #[serde(default = "default_bool_true")]
+    pub heading_attributes: bool,
+}
+
+impl Default for MarkdownSettings {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            smart_punctuation: true,
+            heading_attributes: true,
+        }
+    }
+}
+
+/// Lexically resolves `path` against the working directory (without touching the disk),
+/// so `./posts`, `posts/` and `/abs/posts` compare equal.
+fn normalize_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut out = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => { out.pop(); }
+            other => out.push(other),
+        }
+    }
+    out
 }
 
 // --- Default value providers ---
@@ -164,7 +760,23 @@ fn default_url() -> String { "https://example.com".to_string() }
 fn default_base() -> String { "".to_string() }
 fn default_title() -> String { "a novos site".to_string() }
 fn default_sass_style() -> String { "expanded".to_string() }
+fn default_language() -> String { "en".to_string() }
+fn default_deploy_branch() -> String { "gh-pages".to_string() }
+fn default_deploy_remote() -> String { "origin".to_string() }
+fn default_deploy_message() -> String { "Deploy site".to_string() }
+fn default_slugify_mode() -> String { "unicode".to_string() }
+fn default_content_ignore() -> Vec<String> { vec!["_*".to_string(), ".*".to_string(), "README.md".to_string()] }
+fn default_sort_by() -> String { "date_desc".to_string() }
+fn default_date_format() -> String { crate::dates::DEFAULT_DATE_FORMAT.to_string() }
 fn default_theme() -> String { "base16-ocean.dark".to_string() }
 fn default_bool_true() -> bool { true }
 fn default_bool_false() -> bool { false }
-fn default_posts_per_page() -> usize { 10 }
\ No newline at end of file
+fn default_posts_per_page() -> usize { 10 }
+fn default_server_port() -> u16 { 8080 }
+fn default_server_host() -> String { "0.0.0.0".to_string() }
+fn default_poll_interval() -> u64 { 200 }
+fn default_live_reload_path() -> String { crate::build::DEFAULT_LIVE_RELOAD_PATH.to_string() }
+fn default_feed_item_count() -> usize { 15 }
+fn default_pagination_path() -> String { "page".to_string() }
+fn default_search_content_length() -> usize { 0 }
+fn default_search_snippet_length() -> usize { 140 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+//! Flexible frontmatter date parsing.
+//!
+//! Accepts RFC 3339 / ISO 8601 (with or without time and offset), RFC 2822,
+//! and plain `%Y-%m-%d`. Naive values are interpreted in `site.timezone`
+//! (UTC by default).
+
+use chrono::{DateTime, Datelike, FixedOffset, Locale, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::{collections::HashMap, io, time::SystemTime};
+use tera::Value;
+
+/// Naive date-time layouts tried after the zoned formats.
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Zone applied to dates written without an offset, from `site.timezone`.
+#[derive(Clone, Copy, Debug)]
+pub enum Zone {
+    Named(chrono_tz::Tz),
+    Fixed(FixedOffset),
+}
+
+impl Default for Zone {
+    fn default() -> Self {
+        Zone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+impl Zone {
+    /// Parses an IANA name (`"Europe/Berlin"`), `"UTC"` or a fixed offset (`"+02:00"`).
+    pub fn parse(name: &str) -> Option<Zone> {
+        let name = name.trim();
+        if let Ok(tz) = name.parse::<chrono_tz::Tz>() {
+            return Some(Zone::Named(tz));
+        }
+        name.parse::<FixedOffset>().ok().map(Zone::Fixed)
+    }
+
+    /// Reads `site.timezone`, defaulting to UTC when unset.
+    pub fn from_config(timezone: Option<&str>) -> io::Result<Zone> {
+        match timezone {
+            None => Ok(Zone::default()),
+            Some(name) => Zone::parse(name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("site.timezone: unknown timezone '{}' (use a name like \"Europe/Berlin\" or an offset like \"+02:00\")", name),
+                )
+            }),
+        }
+    }
+
+    /// Reads a wall-clock time in this zone. Times skipped by a DST change fall back to UTC.
+    fn localize(&self, ndt: &NaiveDateTime) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Named(tz) => tz
+                .from_local_datetime(ndt)
+                .earliest()
+                .map(|dt| dt.fixed_offset())
+                .unwrap_or_else(|| Utc.from_utc_datetime(ndt).fixed_offset()),
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(ndt)
+                .single()
+                .unwrap_or_else(|| Utc.from_utc_datetime(ndt).fixed_offset()),
+        }
+    }
+
+    /// The same instant, shown with this zone's offset.
+    pub fn convert(&self, dt: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Named(tz) => dt.with_timezone(tz).fixed_offset(),
+            Zone::Fixed(offset) => dt.with_timezone(offset),
+        }
+    }
+}
+
+/// Structured date fields exposed to templates as `post.date_info`.
+#[derive(Serialize, Clone)]
+pub struct DateInfo {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    /// RFC 3339 representation, suitable for `<time datetime="...">`.
+    pub iso: String,
+    /// Human-friendly display string.
+    pub display: String,
+}
+
+/// Default `site.date_format`, shared by `date_info.display` and the `date_format` filter.
+pub const DEFAULT_DATE_FORMAT: &str = "%B %-d, %Y";
+
+impl DateInfo {
+    /// `display` is `dt` in `format`, localized like the `date_format` filter.
+    pub fn new(dt: &DateTime<FixedOffset>, format: &str, locale: Option<&str>) -> Self {
+        DateInfo {
+            year: dt.year(),
+            month: dt.month(),
+            day: dt.day(),
+            iso: dt.to_rfc3339(),
+            display: format_localized(dt, format, locale),
+        }
+    }
+}
+
+/// Parses a frontmatter date string, returning [None] if no known format matches.
+pub fn parse_date(raw: &str) -> Option<DateTime<FixedOffset>> {
+    parse_date_in(raw, &Zone::default())
+}
+
+/// Like [parse_date], reading dates without an offset in `zone`.
+pub fn parse_date_in(raw: &str, zone: &Zone) -> Option<DateTime<FixedOffset>> {
+    let s = raw.trim().trim_matches('"');
+    if s.is_empty() {
+        return None;
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt);
+    }
+    if let Ok(dt) = DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S %z") {
+        return Some(dt);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) {
+        return Some(dt);
+    }
+    for fmt in NAIVE_DATETIME_FORMATS {
+        if let Ok(ndt) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(zone.localize(&ndt));
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|ndt| zone.localize(&ndt))
+}
+
+/// Converts a filesystem timestamp into the same offset-aware type.
+pub fn from_system_time(t: SystemTime) -> DateTime<FixedOffset> {
+    DateTime::<Utc>::from(t).fixed_offset()
+}
+
+/// Formats `dt` with a strftime pattern, localizing month and day names when
+/// `locale` (e.g. `"es_ES"`) is a known locale.
+pub fn format_localized(dt: &DateTime<FixedOffset>, fmt: &str, locale: Option<&str>) -> String {
+    match locale.and_then(|l| Locale::try_from(l).ok()) {
+        Some(loc) => dt.format_localized(fmt, loc).to_string(),
+        None => dt.format(fmt).to_string(),
+    }
+}
+
+/// Builds the `date_format` Tera filter.
+///
+/// Usage: `{{ post.date | date_format }}` or `{{ post.date | date_format(format="%d/%m/%Y") }}`.
+/// Accepts a raw date string or a `date_info` object. Month and day names are
+/// localized when `locale` (e.g. `"es_ES"`) is a known locale.
+pub fn date_format_filter(
+    default_format: String,
+    locale: Option<String>,
+    zone: Zone,
+) -> impl Fn(&Value, &HashMap<String, Value>) -> tera::Result<Value> + Send + Sync {
+    move |value, args| {
+        let raw = match value {
+            Value::String(s) => s.as_str(),
+            Value::Object(obj) => obj.get("iso").and_then(|v| v.as_str()).unwrap_or_default(),
+            _ => return Err(tera::Error::msg("date_format expects a date string or date_info object")),
+        };
+        let dt = parse_date_in(raw, &zone)
+            .ok_or_else(|| tera::Error::msg(format!("date_format: unrecognized date '{}'", raw)))?;
+
+        let fmt = args
+            .get("format")
+            .and_then(|v| v.as_str())
+            .unwrap_or(default_format.as_str());
+
+        Ok(Value::String(format_localized(&dt, fmt, locale.as_deref())))
+    }
+}
@@ -0,0 +1,181 @@
+//! `novos deploy`: publishes `output_dir` to a static host.
+//!
+//! Each host is a [DeployTarget]; [target_for] picks one from `[deploy] target`.
+//! New hosts only need an implementation and a match arm there.
+
+use crate::config::{Config, DeploySettings};
+use std::{
+    fs, io,
+    path::Path,
+    process::{Command, Output},
+};
+
+/// A destination the built site can be published to.
+pub trait DeployTarget {
+    /// Short name shown in progress output (e.g. `git`).
+    fn name(&self) -> &'static str;
+
+    /// Publishes `output_dir`, returning a one-line summary of where it went.
+    fn deploy(&self, config: &Config) -> io::Result<String>;
+}
+
+/// Commits the output directory to a branch (default `gh-pages`) and pushes it.
+///
+/// The commit is made in a throwaway repository outside `output_dir`, so nothing is
+/// written into the published output. With `force` (the default) the branch is
+/// replaced by exactly one snapshot of the current build; otherwise the deploy is
+/// committed on top of the branch's existing history.
+pub struct GitTarget<'a> {
+    settings: &'a DeploySettings,
+}
+
+/// Mirrors the output directory to `host:path` with `rsync -az`.
+pub struct RsyncTarget<'a> {
+    settings: &'a DeploySettings,
+}
+
+/// Resolves the configured target, or explains what's missing.
+pub fn target_for(settings: &DeploySettings) -> io::Result<Box<dyn DeployTarget + '_>> {
+    match settings.target.as_deref() {
+        Some("git") => Ok(Box::new(GitTarget { settings })),
+        Some("rsync") => Ok(Box::new(RsyncTarget { settings })),
+        Some(other) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown deploy target '{}' (expected \"git\" or \"rsync\")", other),
+        )),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no deploy target configured; set [deploy] target = \"git\" or \"rsync\" in novos.toml",
+        )),
+    }
+}
+
+/// Runs an external command, turning a non-zero exit into an error carrying its stderr.
+fn run(cmd: &mut Command) -> io::Result<Output> {
+    log::debug!("  run {:?}", cmd);
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{:?} failed: {}",
+            cmd,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output)
+}
+
+fn git(dir: &Path) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(dir);
+    cmd
+}
+
+impl GitTarget<'_> {
+    /// `-c key=value` overrides for identity settings the project's git config lacks.
+    /// CI runners often have no identity configured; the fallback lets the commit succeed.
+    fn fallback_identity() -> Vec<String> {
+        [("user.name", "novos"), ("user.email", "novos@localhost")]
+            .into_iter()
+            .filter(|(key, _)| {
+                !git(Path::new("."))
+                    .args(["config", key])
+                    .output()
+                    .is_ok_and(|o| o.status.success())
+            })
+            .flat_map(|(key, fallback)| ["-c".to_string(), format!("{}={}", key, fallback)])
+            .collect()
+    }
+
+    /// A git command for the scratch repository whose work tree is `out`.
+    fn scratch_git(scratch: &Path, out: &Path, identity: &[String]) -> Command {
+        let mut cmd = Command::new("git");
+        cmd.arg("--git-dir").arg(scratch).arg("--work-tree").arg(out).args(identity);
+        cmd
+    }
+}
+
+impl DeployTarget for GitTarget<'_> {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn deploy(&self, config: &Config) -> io::Result<String> {
+        let out = &config.output_dir;
+        let remote = self.settings.remote.as_str();
+        let branch = self.settings.branch.as_str();
+
+        // An output directory that is itself a clone (of gh-pages, say) is the user's
+        // repository; committing over it would clobber or embed it.
+        if out.join(".git").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} is a git repository; deploy it yourself or remove its .git before using target = \"git\"",
+                    out.display()
+                ),
+            ));
+        }
+
+        // Push to the project's remote URL so the output repo needs no remotes of its own.
+        let url = run(git(Path::new(".")).args(["remote", "get-url", remote]))?;
+        let url = String::from_utf8_lossy(&url.stdout).trim().to_string();
+
+        let scratch = std::env::temp_dir().join(format!("novos-deploy-{}", std::process::id()));
+        if scratch.exists() {
+            fs::remove_dir_all(&scratch)?;
+        }
+        let identity = Self::fallback_identity();
+        let scratch_git = || Self::scratch_git(&scratch, out, &identity);
+        let head = format!("refs/heads/{}", branch);
+        let result = (|| {
+            run(scratch_git().args(["init", "-q"]))?;
+            run(scratch_git().args(["symbolic-ref", "HEAD", &head]))?;
+            if !self.settings.force {
+                // A missing branch just means this is the first deploy.
+                if run(scratch_git().args(["fetch", "-q", &url, branch])).is_ok() {
+                    run(scratch_git().args(["update-ref", &head, "FETCH_HEAD"]))?;
+                }
+            }
+            run(scratch_git().args(["add", "-A"]))?;
+            run(scratch_git().args(["commit", "-q", "--allow-empty", "-m", &self.settings.message]))?;
+            let refspec = format!("HEAD:{}", branch);
+            let mut push = scratch_git();
+            push.args(["push", "-q"]);
+            if self.settings.force {
+                push.arg("--force");
+            }
+            run(push.arg(&url).arg(&refspec))
+        })();
+        let _ = fs::remove_dir_all(&scratch);
+        result?;
+
+        Ok(format!("pushed {} to {} ({})", out.display(), branch, url))
+    }
+}
+
+impl DeployTarget for RsyncTarget<'_> {
+    fn name(&self) -> &'static str {
+        "rsync"
+    }
+
+    fn deploy(&self, config: &Config) -> io::Result<String> {
+        let (Some(host), Some(path)) = (&self.settings.host, &self.settings.path) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "rsync deploy needs [deploy] host and path",
+            ));
+        };
+        let dest = format!("{}:{}", host, path);
+        // Trailing slash: copy the directory's contents, not the directory itself.
+        let src = format!("{}/", config.output_dir.display());
+
+        let mut cmd = Command::new("rsync");
+        cmd.arg("-az");
+        if self.settings.delete {
+            cmd.arg("--delete");
+        }
+        run(cmd.arg(&src).arg(&dest))?;
+
+        Ok(format!("synced {} to {}", config.output_dir.display(), dest))
+    }
+}
@@ -0,0 +1,50 @@
+//! User-defined lifecycle commands from the `[hooks]` config section.
+
+use console::style;
+use std::{io, process::Command};
+
+/// Runs each command through the platform shell, in order, stopping at the first failure.
+///
+/// Output is captured and echoed line by line, prefixed with `stage`, so it's clear
+/// which hook printed what. A failing hook's stderr is logged as errors, so its
+/// diagnostics still show under `--quiet`.
+pub fn run_hooks(stage: &str, commands: &[String]) -> io::Result<()> {
+    for cmd in commands {
+        log::debug!("  {} {}", stage, cmd);
+
+        let output = shell(cmd).output().map_err(|e| {
+            io::Error::other(format!("{} hook `{}` failed to start: {}", stage, cmd, e))
+        })?;
+
+        let prefix = style(format!("[{}]", stage)).dim();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            log::info!("{} {}", prefix, line);
+        }
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            if output.status.success() {
+                log::info!("{} {}", prefix, line);
+            } else {
+                log::error!("{} {}", prefix, line);
+            }
+        }
+
+        if !output.status.success() {
+            return Err(io::Error::other(format!("{} hook `{}` exited with {}", stage, cmd, output.status)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn shell(cmd: &str) -> Command {
+    let mut c = Command::new("cmd");
+    c.args(["/C", cmd]);
+    c
+}
+
+#[cfg(not(windows))]
+fn shell(cmd: &str) -> Command {
+    let mut c = Command::new("sh");
+    c.args(["-c", cmd]);
+    c
+}
@@ -0,0 +1,55 @@
+//! Library interface to the `novos` engine, for embedding builds in other tools.
+//!
+//! ```no_run
+//! let config: novos::Config = toml::from_str(&std::fs::read_to_string("novos.toml")?)?;
+//! novos::build(&config)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The `novos` binary is a thin CLI over these modules.
+
+pub mod build;
+pub mod cache;
+pub mod config;
+pub mod dates;
+pub mod deploy;
+mod hooks;
+pub mod links;
+pub mod logger;
+mod manifest;
+mod progress;
+pub mod models;
+pub mod parser;
+mod rss;
+mod seo;
+mod shortcodes;
+mod sitemap;
+mod timings;
+pub mod server;
+pub mod watch;
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+pub use build::{perform_build, perform_build_incremental, BuildOptions};
+pub use cache::BuildCache;
+pub use config::Config;
+pub use models::Post;
+pub use parser::{markdown_options, parse_frontmatter, render_markdown, slugify, strip_markdown, RenderOptions};
+
+/// Runs a full, one-off production build of `config` (no live-reload, no caching across calls).
+///
+/// Errors, including templates that fail to parse, are returned rather than ending the
+/// process. To fail on warnings, call [perform_build] with [BuildOptions::strict] and
+/// install [logger] first: it is what counts them.
+pub fn build(config: &Config) -> io::Result<()> {
+    perform_build(
+        config,
+        Arc::new(Mutex::new(SystemTime::UNIX_EPOCH)),
+        &BuildCache::default(),
+        &BuildOptions::default(),
+    )
+}
@@ -0,0 +1,125 @@
+//! Internal link checking for `novos check --links`, and the asset report run after builds.
+//!
+//! Scans every generated HTML file for `href`/`src` targets and verifies that
+//! internal ones resolve to a file in `output_dir`, including `#fragment` ids.
+//! External URLs are not fetched.
+
+use crate::config::Config;
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// One unresolved link, reported against the page that contains it.
+pub struct BrokenLink {
+    /// Page containing the link, relative to `output_dir`.
+    pub page: PathBuf,
+    pub target: String,
+    pub reason: &'static str,
+}
+
+/// Schemes and prefixes that never point into the output directory.
+fn is_external(link: &str) -> bool {
+    link.starts_with("//")
+        || link.contains("://")
+        || ["mailto:", "tel:", "data:", "javascript:", "sms:"].iter().any(|p| link.starts_with(p))
+}
+
+/// Maps a URL path onto a file, honoring pretty URLs (`/about` -> `about.html`
+/// or `about/index.html`).
+fn resolve_file(candidate: &Path) -> Option<PathBuf> {
+    if candidate.is_file() {
+        return Some(candidate.to_path_buf());
+    }
+    let index = candidate.join("index.html");
+    if index.is_file() {
+        return Some(index);
+    }
+    let html = candidate.with_extension("html");
+    html.is_file().then_some(html)
+}
+
+/// Checks all internal links (and their `#anchors`) under `config.output_dir`.
+pub fn check_links(config: &Config) -> Vec<BrokenLink> {
+    let attr_re = Regex::new(r#"(?i)\s(?:href|src)\s*=\s*["']([^"']*)["']"#).unwrap();
+    scan(config, &attr_re, true)
+}
+
+/// Checks that local `src`/`poster` references (images, scripts, media) exist in the output.
+pub fn check_assets(config: &Config) -> Vec<BrokenLink> {
+    let attr_re = Regex::new(r#"(?i)\s(?:src|poster)\s*=\s*["']([^"']*)["']"#).unwrap();
+    scan(config, &attr_re, false)
+}
+
+fn scan(config: &Config, attr_re: &Regex, check_fragments: bool) -> Vec<BrokenLink> {
+    let id_re = Regex::new(r#"(?i)\s(?:id|name)\s*=\s*["']([^"']+)["']"#).unwrap();
+    let out_dir = &config.output_dir;
+    let base = config.base.trim_end_matches('/');
+
+    let pages: Vec<PathBuf> = walkdir::WalkDir::new(out_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "html"))
+        .map(|e| e.into_path())
+        .collect();
+
+    // Target page -> ids it defines, read lazily and once.
+    let mut ids: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+    let mut broken = Vec::new();
+    for page in &pages {
+        let Ok(html) = fs::read_to_string(page) else { continue };
+        let rel_page = page.strip_prefix(out_dir).unwrap_or(page).to_path_buf();
+
+        for caps in attr_re.captures_iter(&html) {
+            let link = caps[1].trim();
+            if link.is_empty() || is_external(link) {
+                continue;
+            }
+            let (path_part, fragment) = match link.split_once('#') {
+                Some((p, f)) => (p, Some(f)),
+                None => (link, None),
+            };
+            let path_part = path_part.split('?').next().unwrap_or_default();
+
+            let target_file = if path_part.is_empty() {
+                Some(page.clone())
+            } else if let Some(root_rel) = path_part.strip_prefix('/') {
+                let root_rel = match base.strip_prefix('/') {
+                    Some(b) if !b.is_empty() => root_rel.strip_prefix(b).map(|r| r.trim_start_matches('/')),
+                    _ => Some(root_rel),
+                };
+                match root_rel {
+                    Some(r) => resolve_file(&out_dir.join(r)),
+                    None => {
+                        broken.push(BrokenLink { page: rel_page.clone(), target: link.to_string(), reason: "outside site base" });
+                        continue;
+                    }
+                }
+            } else {
+                resolve_file(&page.parent().unwrap_or(out_dir).join(path_part))
+            };
+
+            let Some(target_file) = target_file else {
+                broken.push(BrokenLink { page: rel_page.clone(), target: link.to_string(), reason: "missing file" });
+                continue;
+            };
+
+            let is_html = target_file.extension().is_some_and(|ext| ext == "html");
+            if let Some(fragment) = fragment.filter(|f| check_fragments && !f.is_empty() && is_html) {
+                let found = ids
+                    .entry(target_file.clone())
+                    .or_insert_with(|| {
+                        let html = fs::read_to_string(&target_file).unwrap_or_default();
+                        id_re.captures_iter(&html).map(|c| c[1].to_string()).collect()
+                    })
+                    .contains(fragment);
+                if !found {
+                    broken.push(BrokenLink { page: rel_page.clone(), target: link.to_string(), reason: "missing anchor" });
+                }
+            }
+        }
+    }
+    broken
+}
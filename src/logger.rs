@@ -0,0 +1,70 @@
+//! Terminal logger behind the `log` facade.
+//!
+//! Levels map onto the CLI flags: `--quiet` shows warnings and errors, the default
+//! adds progress (`info`), and `--verbose` adds per-file detail (`debug`).
+//! All color goes through `console` styles, which drop their escape codes when
+//! the stream isn't a TTY, when `NO_COLOR` is set, or under `--no-color`, so piped
+//! and CI output stays plain.
+
+use console::style;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Warnings emitted so far, including ones hidden by the level filter; `--strict` reads this.
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of warnings logged by novos since the process started.
+pub fn warning_count() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+struct CliLogger;
+
+impl Log for CliLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependencies log through the same facade; only show our own events.
+        metadata.target().starts_with("novos") && metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() == Level::Warn && record.target().starts_with("novos") {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let msg = record.args().to_string();
+        match record.level() {
+            Level::Error => eprintln!("{} {}", style("error").red().for_stderr(), msg),
+            Level::Warn => eprintln!("{} {}", style("warning").yellow().for_stderr(), msg),
+            Level::Info => println!("{}", msg),
+            Level::Debug | Level::Trace => {
+                // Dim the leading label ("  compiling", "[2/5]") like the step output.
+                let indent = msg.len() - msg.trim_start().len();
+                let end = msg[indent..].find(' ').map_or(msg.len(), |i| indent + i);
+                println!("{}{}", style(&msg[..end]).dim(), &msg[end..]);
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger and decides on color. Call once, before any output.
+pub fn init(verbose: bool, quiet: bool, no_color: bool) {
+    // https://no-color.org: any non-empty value disables color.
+    let env_no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || env_no_color {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, true) => LevelFilter::Debug,
+        (false, false) => LevelFilter::Info,
+    };
+    if log::set_boxed_logger(Box::new(CliLogger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -16,12 +16,8 @@
 //! - **Markdown:** `pulldown-cmark` (CommonMark compliant, yay!)
 //! - **License:** 3-Clause BSD
 
-mod config;
-mod models;
-mod parser;
-mod rss;
-mod build;
-mod server;
+use novos::{build, cache, config, deploy, links, logger, server, watch};
+use console::style;
 
 use clap::{Parser as ClapParser, Subcommand};
 use rust_embed::RustEmbed;
@@ -58,20 +54,82 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
     /// Enable verbose logging output.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
+    /// Only print warnings and errors.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Fail on any warning (bad dates, missing templates, broken links or assets).
+    #[arg(long, global = true)]
+    strict: bool,
+    /// Disable colored output (also honored: the `NO_COLOR` environment variable).
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Use this config file instead of `./novos.toml`. The project (every relative
+    /// path in the config, plus templates/, data/ and themes/) is read from its directory.
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Clone)]
 enum Commands {
     /// Compiles the project into a static site.
-    Build,
+    Build {
+        /// Parse and render everything, list the files that would be written, but write nothing.
+        #[arg(long)]
+        dry_run: bool,
+        /// Write the site to this directory instead of `output_dir` from novos.toml.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Keep running and rebuild on file changes (no HTTP server, no live-reload).
+        #[arg(short, long, conflicts_with = "dry_run")]
+        watch: bool,
+        /// Print how long each build phase took (static copy, sass, images, rendering, feeds, ...).
+        #[arg(long)]
+        timings: bool,
+        /// Include drafts and future posts, mark every page `noindex`, and write to
+        /// `<output_dir>-preview` (unless --output is given) so the real build stays clean.
+        #[arg(long)]
+        preview: bool,
+    },
     /// Starts a local server with live-reloading.
     #[command(alias = "server")]
     Serve {
-        /// Port to listen on.
-        #[arg(short, long, default_value_t = 8080)]
-        port: u16,
+        /// Port to listen on [default: `[server] port`, else 8080].
+        #[arg(short, long)]
+        port: Option<u16>,
+        /// Address to bind [default: `[server] host`, else 0.0.0.0].
+        #[arg(long)]
+        host: Option<String>,
+        /// Open the site in the default browser once the server is up.
+        #[arg(long)]
+        open: bool,
+        /// WebSocket route used for live-reload (change it if your site uses /novos/live)
+        /// [default: `[server] live_reload_path`].
+        #[arg(long)]
+        live_path: Option<String>,
+        /// Serve over https:// (and live-reload over wss://). Uses --cert/--key when
+        /// given, otherwise a self-signed certificate for localhost.
+        #[arg(long)]
+        tls: bool,
+        /// PEM certificate chain; implies --tls.
+        #[arg(long, requires = "key")]
+        cert: Option<PathBuf>,
+        /// PEM private key; implies --tls.
+        #[arg(long, requires = "cert")]
+        key: Option<PathBuf>,
+    },
+    /// Builds the site and validates the output.
+    Check {
+        /// Verify that internal `href`/`src` links (and `#anchors`) resolve to generated files.
+        #[arg(long)]
+        links: bool,
+    },
+    /// Builds the site and publishes it to the target configured under `[deploy]`.
+    Deploy {
+        /// Publish the existing output directory without rebuilding first.
+        #[arg(long)]
+        no_build: bool,
     },
      /// Scaffolds a new project.
 
@@ -89,37 +147,132 @@ enum Commands {
 async fn main() -> anyhow::Result<()> {
     let start = Instant::now();
     let cli = Cli::parse();
+    logger::init(cli.verbose, cli.quiet, cli.no_color);
 
     match cli.command {
         Commands::Init { directory, bare } => {
-            println!("novos init v{}", env!("CARGO_PKG_VERSION"));
+            log::info!("novos init v{}", env!("CARGO_PKG_VERSION"));
             init_project(&directory, bare)?;
-            println!("\n\x1b[36msuccess\x1b[0m Project initialized in '{}'.", directory);
-            println!("Done in {:.2}s.", start.elapsed().as_secs_f32());
+            log::info!("\n{} Project initialized in '{}'.", style("success").cyan(), directory);
+            log::info!("Done in {:.2}s.", start.elapsed().as_secs_f32());
         }
         _ => {
             // Commands that require novos.toml
-            let cfg_str = fs::read_to_string("novos.toml").map_err(|_| {
-                anyhow::anyhow!(
-                    "\x1b[31mError: novos.toml not found. Run 'novos init' to begin.\x1b[0m"
-                )
+            let config_path = cli.config.clone().unwrap_or_else(|| PathBuf::from("novos.toml"));
+            let cfg_str = fs::read_to_string(&config_path).map_err(|_| match &cli.config {
+                Some(path) => anyhow::anyhow!("config file '{}' not found.", path.display()),
+                None => anyhow::anyhow!("novos.toml not found. Run 'novos init' to begin."),
             })?;
 
-            let config: config::Config = toml::from_str(&cfg_str)?;
+            let mut config: config::Config = toml::from_str(&cfg_str)?;
+
+            // Everything resolves relative to the config's directory, so build from there.
+            // --output stays relative to where novos was invoked.
+            let invoked_from = std::env::current_dir()?;
+            if let Some(project_dir) = config_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                std::env::set_current_dir(project_dir).map_err(|e| {
+                    anyhow::anyhow!("cannot enter project directory '{}': {}", project_dir.display(), e)
+                })?;
+                log::debug!("  project directory: {}", project_dir.display());
+            }
+
+            if let Commands::Build { output: Some(ref out), .. } = cli.command {
+                config.output_dir = if cli.config.is_some() { invoked_from.join(out) } else { out.clone() };
+                if let Some(src) = config.output_clobbers_source() {
+                    anyhow::bail!(
+                        "--output '{}' would overwrite source directory '{}'.",
+                        out.display(),
+                        src.display()
+                    );
+                }
+            } else if let Commands::Build { preview: true, .. } = cli.command {
+                // Collecting the components drops a trailing slash, so "dist/" becomes "dist-preview".
+                let mut preview_dir = config.output_dir.components().collect::<PathBuf>().into_os_string();
+                preview_dir.push("-preview");
+                config.output_dir = preview_dir.into();
+            }
             let last_run = Arc::new(Mutex::new(SystemTime::UNIX_EPOCH));
 
             match cli.command {
-                Commands::Build => {
+                Commands::Build { dry_run, watch: watch_mode, timings, preview, .. } => {
                     // is_dev is false for standard builds
-                    build::perform_build(&config, last_run, cli.verbose, false)?;
-                    println!("\x1b[32msuccess\x1b[0m Build complete in {:.2}s.", start.elapsed().as_secs_f32());
+                    let opts = build::BuildOptions { is_dev: false, dry_run, strict: cli.strict, timings, preview, ..Default::default() };
+                    build::perform_build(&config, Arc::clone(&last_run), &cache::BuildCache::default(), &opts)?;
+                    if watch_mode {
+                        log::info!("{} Initial build complete in {:.2}s.", style("success").green(), start.elapsed().as_secs_f32());
+                        return watch::watch(config, last_run, opts).await;
+                    }
+                    if dry_run {
+                        log::info!("{} Dry run complete in {:.2}s.", style("success").green(), start.elapsed().as_secs_f32());
+                    } else {
+                        log::info!("{} Build complete in {:.2}s.", style("success").green(), start.elapsed().as_secs_f32());
+                    }
                 }
-                Commands::Serve { port } => {
-                    println!("novos serve v{}", env!("CARGO_PKG_VERSION"));
-                    println!("\x1b[2m[1/1]\x1b[0m Starting server on port {}...", port);
+                Commands::Serve { port, host, open, live_path, tls, cert, key } => {
+                    // Flags win over `[server]` in novos.toml.
+                    let mut config = config;
+                    if let Some(port) = port {
+                        config.server.port = port;
+                    }
+                    if let Some(host) = host {
+                        config.server.host = host;
+                    }
+                    if let Some(live_path) = live_path {
+                        config.server.live_reload_path = live_path;
+                    }
+                    config.server.open |= open;
+
+                    log::info!("novos serve v{}", env!("CARGO_PKG_VERSION"));
+                    log::info!("{} Starting server on port {}...", style("[1/1]").dim(), config.server.port);
                     
-                    // We pass 'true' for is_dev to enable the live-reload script injection
-                    server::serve(config, last_run, port, cli.verbose).await?;
+                    if !config.server.live_reload_path.starts_with('/') {
+                        anyhow::bail!("the live-reload path must start with '/'.");
+                    }
+                    let tls = match (cert, key) {
+                        (Some(cert), Some(key)) => Some(server::Tls::Files { cert, key }),
+                        _ if tls => Some(server::Tls::SelfSigned),
+                        _ => None,
+                    };
+                    server::serve(config, last_run, tls).await?;
+                }
+                Commands::Check { links: check_links } => {
+                    log::info!("novos check v{}", env!("CARGO_PKG_VERSION"));
+                    let opts = build::BuildOptions { strict: cli.strict, ..Default::default() };
+                    build::perform_build(&config, Arc::clone(&last_run), &cache::BuildCache::default(), &opts)?;
+
+                    if check_links {
+                        log::info!("{} Checking internal links...", style("[1/1]").dim());
+                        let broken = links::check_links(&config);
+                        for link in &broken {
+                            log::error!(
+                                "{}: {} ({})",
+                                link.page.display(),
+                                link.target,
+                                link.reason
+                            );
+                        }
+                        if !broken.is_empty() {
+                            anyhow::bail!("{} broken link(s).", broken.len());
+                        }
+                    }
+                    log::info!("{} Check passed in {:.2}s.", style("success").green(), start.elapsed().as_secs_f32());
+                }
+                Commands::Deploy { no_build } => {
+                    log::info!("novos deploy v{}", env!("CARGO_PKG_VERSION"));
+                    let target = deploy::target_for(&config.deploy)?;
+                    if !no_build {
+                        let opts = build::BuildOptions { strict: cli.strict, ..Default::default() };
+                        build::perform_build(&config, Arc::clone(&last_run), &cache::BuildCache::default(), &opts)?;
+                    }
+                    if !config.output_dir.is_dir() {
+                        anyhow::bail!(
+                            "output directory '{}' does not exist; run without --no-build.",
+                            config.output_dir.display()
+                        );
+                    }
+                    log::info!("{} Deploying with {}...", style("[1/1]").dim(), target.name());
+                    let summary = target.deploy(&config)?;
+                    log::info!("{} {} in {:.2}s.", style("success").green(), summary, start.elapsed().as_secs_f32());
                 }
                 _ => unreachable!(),
             }
@@ -190,13 +343,10 @@ fn init_project(target_dir: &str, bare: bool) -> anyhow::Result<()> {
     let mut url = "https://example.com".to_string();
     let mut title = "novos site".to_string();
     let mut author = "admin".to_string();
-    let mut use_sass = true;
-    let mut use_syntect = true;
-    let mut gen_search = true;
-    let mut gen_rss = true;
     let mut clean_out = true;
-    let mut convert_to_webp = false;
     let mut minify = false;
+    // Set by the prompts below, or all off for `--bare`.
+    let (use_sass, use_syntect, gen_search, gen_rss, convert_to_webp);
 
     // --- Interactive Prompts ---
     if !bare {
@@ -220,7 +370,7 @@ fn init_project(target_dir: &str, bare: bool) -> anyhow::Result<()> {
         convert_to_webp = false;
     }
 
-    println!("\n\x1b[2m[1/2]\x1b[0m Generating novos.toml...");
+    log::info!("\n{} Generating novos.toml...", style("[1/2]").dim());
 
     // Optimization: If sass is disabled, we might want to default to expanded 
     // or just leave it, but 'compressed' is a good default for prod.
@@ -259,7 +409,7 @@ sass_style = "{sass_style}"
     }
     fs::write(base_path.join("novos.toml"), toml_content)?;
 
-    println!("\x1b[2m[2/2]\x1b[0m Extracting assets...");
+    log::info!("{} Extracting assets...", style("[2/2]").dim());
 
     if bare {
         extract_assets::<BlankAsset>(&base_path)?;
@@ -0,0 +1,90 @@
+//! Machine-readable build summary (`manifest.json`).
+//!
+//! Gives deploy scripts and link checkers a stable list of everything a build
+//! produced, without having to scrape the generated HTML.
+
+use crate::{config::Config, models::Post};
+use serde::Serialize;
+use std::{path::Path, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+/// A single generated page.
+#[derive(Serialize)]
+pub struct PageEntry {
+    /// `post`, `page`, `collection`, `tag` or `index`.
+    pub kind: &'static str,
+    /// Source file the page was rendered from, if any.
+    pub source: Option<String>,
+    /// Path relative to the output directory.
+    pub output: String,
+    pub permalink: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    /// Source modification time as a UNIX timestamp.
+    pub mtime: Option<u64>,
+}
+
+impl PageEntry {
+    pub fn post(post: &Post, config: &Config) -> Self {
+        PageEntry {
+            kind: match (&post.collection, post.is_page) {
+                (Some(_), _) => "collection",
+                (None, true) => "page",
+                (None, false) => "post",
+            },
+            source: Some(post.source_path.to_string_lossy().into_owned()),
+            output: normalize(Path::new(&config.output_file_for(post))),
+            permalink: config.permalink_for(post),
+            title: post.title.clone(),
+            tags: post.tags.clone(),
+            mtime: post.mtime.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs()),
+        }
+    }
+
+    /// A page with no source file, identified by its route (`tags/rust`, `page/2`, `""`).
+    pub fn generated(kind: &'static str, route: &str, title: &str, config: &Config) -> Self {
+        PageEntry {
+            kind,
+            source: None,
+            output: normalize(Path::new(&config.build.url_style.file(route))),
+            permalink: config.route_url(route),
+            title: title.to_string(),
+            tags: Vec::new(),
+            mtime: None,
+        }
+    }
+}
+
+/// The root of `manifest.json`.
+#[derive(Serialize)]
+pub struct Manifest {
+    pub version: &'static str,
+    pub generated_at: u64,
+    pub build_time_ms: u128,
+    pub pages: Vec<PageEntry>,
+    /// Feed, search and sitemap files, relative to the output directory.
+    pub feeds: Vec<String>,
+}
+
+impl Manifest {
+    /// Assembles the manifest with pages sorted by output path so diffs stay stable.
+    pub fn new(mut pages: Vec<PageEntry>, mut feeds: Vec<String>, elapsed: Duration) -> Self {
+        pages.sort_by(|a, b| a.output.cmp(&b.output));
+        feeds.sort();
+        Manifest {
+            version: env!("CARGO_PKG_VERSION"),
+            generated_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            build_time_ms: elapsed.as_millis(),
+            pages,
+            feeds,
+        }
+    }
+}
+
+/// Forward-slash relative path regardless of platform.
+fn normalize(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter(|c| *c != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -1,12 +1,142 @@
-use std::time::SystemTime;
+use std::{path::PathBuf, time::SystemTime};
 use serde::Serialize;
+use chrono::{DateTime, FixedOffset, Utc};
+use crate::{config::Config, dates::DateInfo};
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct Post {
     pub slug: String,
     pub title: String,
     pub date: String,
+    /// Parsed publish date; falls back to the file's mtime when `date` is missing.
+    pub datetime: DateTime<FixedOffset>,
+    /// Last edit, from `updated`/`modified` frontmatter or the file's mtime.
+    pub updated: DateTime<FixedOffset>,
+    /// `draft: true` frontmatter; drafts only appear under `novos serve`.
+    pub draft: bool,
+    /// Optional `expires` date after which production builds drop this item.
+    pub expires: Option<DateTime<FixedOffset>>,
+    /// Structured date fields (`year`, `month`, `day`, `iso`, `display`) for templates.
+    pub date_info: DateInfo,
     pub tags: Vec<String>,
+    /// Author keys from the `author`/`authors` frontmatter; empty means `site.author`.
+    pub authors: Vec<String>,
+    /// Short summary from the `excerpt` frontmatter key, or derived from the body.
+    pub excerpt: String,
+    /// Optional `cover_image` frontmatter key used for social previews.
+    pub cover_image: Option<String>,
     pub raw_content: String,
+    /// `raw_content` with Markdown syntax stripped, derived once at parse time for
+    /// word counts, excerpts, search and feeds. Not exposed to templates.
+    #[serde(skip)]
+    pub plain_text: String,
+    /// The frontmatter block exactly as written (without its delimiters), for themes
+    /// that parse custom sections themselves. Empty when the file has none.
+    pub raw_frontmatter: String,
+    /// Words in the rendered text (Markdown syntax stripped).
+    pub word_count: usize,
+    /// Estimated minutes to read, at least 1.
+    pub reading_time: usize,
     pub mtime: SystemTime,
+    /// The Markdown file this post was read from.
+    pub source_path: PathBuf,
+    /// Folder under `posts_dir`/`pages_dir` holding the source (`2024/rust`, forward
+    /// slashes), empty at the top level.
+    pub subdir: String,
+    /// Language code, from a `slug.<lang>.md` suffix or `site.default_language`.
+    pub lang: String,
+    /// True for standalone pages loaded from `pages_dir`.
+    pub is_page: bool,
+    /// Name of the `[[collections]]` entry this item was loaded from; `None` for posts and pages.
+    pub collection: Option<String>,
+    /// Menus this item appears in (`menu: main, footer`).
+    pub menus: Vec<String>,
+    /// Ordering within menus; lower comes first.
+    pub weight: i64,
+    /// Absolute `canonical` frontmatter URL for cross-posted content.
+    pub canonical: Option<String>,
+    /// Old URLs (`aliases` frontmatter) that should redirect to this item.
+    pub aliases: Vec<String>,
+    /// Media attachment (podcast episode, video) for the RSS `<enclosure>`.
+    pub enclosure: Option<Enclosure>,
+    /// Frontmatter keys novos doesn't use itself, available as `post.extra.<key>`.
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// Per-item `template` frontmatter override (e.g. `landing.html`).
+    pub template: Option<String>,
+    /// Per-item `in_feed` frontmatter override.
+    pub in_feed: Option<bool>,
+    /// Per-item `in_sitemap` frontmatter override.
+    pub in_sitemap: Option<bool>,
+    /// Per-item `minify` frontmatter override of `build.minify_html`.
+    pub minify: Option<bool>,
+    /// Robots directives (`noindex, nofollow`) from the `robots` frontmatter key or the
+    /// `noindex`/`nofollow` booleans. `None` means indexable.
+    pub robots: Option<String>,
+}
+
+/// An `enclosure` frontmatter block:
+///
+/// ```yaml
+/// enclosure:
+///   url: /audio/episode-1.mp3
+///   type: audio/mpeg
+///   length: 12345678
+///   duration: 42:17
+/// ```
+#[derive(Serialize, Clone, Default)]
+pub struct Enclosure {
+    pub url: String,
+    /// Size in bytes. Filled from the output file when omitted and the URL is local.
+    pub length: Option<u64>,
+    /// MIME type; guessed from the extension when omitted.
+    pub mime_type: Option<String>,
+    /// iTunes `<itunes:duration>` (e.g. `42:17`).
+    pub duration: Option<String>,
+}
+
+/// One navigation link in a named menu.
+#[derive(Serialize, Clone)]
+pub struct MenuEntry {
+    pub title: String,
+    pub url: String,
+    pub weight: i64,
+}
+
+impl Post {
+    /// True once the `expires` date has passed.
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|e| e < Utc::now())
+    }
+
+    /// True while the publish date is still ahead.
+    pub fn is_future(&self) -> bool {
+        self.datetime > Utc::now()
+    }
+
+    /// Whether production builds publish this item: not a draft and not scheduled.
+    pub fn is_published(&self) -> bool {
+        !self.draft && !self.is_future()
+    }
+
+    /// Whether this item belongs in RSS. Posts default to yes, pages follow `site.pages_in_feed`.
+    pub fn in_feed(&self, config: &Config) -> bool {
+        self.in_feed.unwrap_or(!self.is_page || config.site.pages_in_feed)
+    }
+
+    /// Whether this item belongs in `sitemap.xml`. Pages follow `site.pages_in_sitemap`;
+    /// `noindex` items are always left out.
+    pub fn in_sitemap(&self, config: &Config) -> bool {
+        !self.is_noindex() && self.in_sitemap.unwrap_or(!self.is_page || config.site.pages_in_sitemap)
+    }
+
+    /// Whether the robots directives ask search engines not to index this item.
+    pub fn is_noindex(&self) -> bool {
+        self.robots.as_deref().is_some_and(|r| r.split(',').any(|d| d.trim().eq_ignore_ascii_case("noindex")))
+    }
+
+    /// Whether this item's HTML is minified. `minify: false` keeps whitespace-sensitive
+    /// markup exactly as rendered.
+    pub fn minify(&self, config: &Config) -> bool {
+        self.minify.unwrap_or(config.build.minify_html)
+    }
 }
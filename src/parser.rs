@@ -1,105 +1,683 @@
-use crate::models::Post;
-use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
-use std::{time::SystemTime};
+use crate::{config::MarkdownSettings, dates, models::{Enclosure, Post}, seo::escape_attr};
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd, TextMergeStream};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{path::PathBuf, time::SystemTime};
 use tera::Tera;
 
 // Syntect imports
-use syntect::highlighting::Theme;
-use syntect::html::highlighted_html_for_string;
-use syntect::parsing::SyntaxSet;
+use crate::cache::Highlighter;
+use syntect::html::{css_for_theme_with_class_style, highlighted_html_for_string, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Rendering switches beyond pulldown-cmark's own options, derived once per build
+/// from `[build]`.
+pub struct RenderOptions<'a> {
+    /// `None` leaves code blocks to pulldown-cmark (`use_syntect = false`).
+    pub highlighter: Option<&'a Highlighter>,
+    /// Emit `class` attributes instead of inline styles (`syntax_css_classes`).
+    pub css_classes: bool,
+    /// Pass ```` ```mermaid ```` blocks through for client-side rendering.
+    pub mermaid: bool,
+    /// Replace `:shortcode:` emoji names in text (`build.emoji`).
+    pub emoji: bool,
+    /// Language for fenced blocks without an info string (`build.default_code_language`).
+    pub default_lang: Option<&'a str>,
+}
 
 /// Initializes the Tera engine. 
 /// It's best to call this once at the start of your program.
-pub fn init_tera(template_dir: &str) -> Tera {
-    let mut tera = match Tera::new(&format!("{}/**/*", template_dir)) {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("Tera parsing error(s): {}", e);
-            std::process::exit(1);
+///
+/// A template that fails to parse is returned as an error (with Tera's full cause
+/// chain in the message) rather than aborting, so library callers stay alive.
+pub fn init_tera(template_dir: &str) -> std::io::Result<Tera> {
+    let mut tera = Tera::new(&format!("{}/**/*", template_dir)).map_err(|e| {
+        let mut message = format!("Tera parsing error(s): {}", e);
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            message.push_str(&format!("\n  {}", cause));
+            source = cause.source();
         }
-    };
+        std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+    })?;
     // We disable auto-escaping because we are injecting pre-rendered 
     // HTML from pulldown-cmark and syntect.
     tera.autoescape_on(vec![]);
-    tera
+    Ok(tera)
 }
 
+/// Average reading speed used for `reading_time`.
+const WORDS_PER_MINUTE: usize = 200;
+
 /// Parses frontmatter from a file and returns a Post struct.
-pub fn parse_frontmatter(raw: &str, slug: &str, mtime: SystemTime) -> Post {
+///
+/// Dates written without an offset are read in `zone` (`site.timezone`).
+pub fn parse_frontmatter(raw: &str, slug: &str, mtime: SystemTime, zone: &dates::Zone) -> Post {
+    parse_frontmatter_with_defaults(raw, slug, mtime, zone, &Map::new())
+}
+
+/// [parse_frontmatter] with directory defaults (`_defaults.toml`) merged under the
+/// file's own frontmatter; keys the file sets itself always win.
+pub fn parse_frontmatter_with_defaults(
+    raw: &str,
+    slug: &str,
+    mtime: SystemTime,
+    zone: &dates::Zone,
+    defaults: &Map<String, Value>,
+) -> Post {
     let (mut title, mut date, mut tags) = (slug.to_string(), String::new(), Vec::new());
-    let mut content = raw.to_string();
-
-    if raw.starts_with("---") {
-        let parts: Vec<&str> = raw.splitn(3, "---").collect();
-        if parts.len() == 3 {
-            for line in parts[1].lines() {
-                if let Some((k, v)) = line.split_once(':') {
-                    match k.trim() {
-                        "title" => title = v.trim().trim_matches('"').to_string(),
-                        "date" => date = v.trim().to_string(),
-                        "tags" => {
-                            tags = v
-                                .split(',')
-                                .map(|s| s.trim().trim_matches('"').to_string())
-                                .filter(|s| !s.is_empty())
-                                .collect();
-                        }
-                        _ => {}
-                    }
+    let (mut excerpt, mut cover_image) = (None, None);
+    let mut authors = Vec::new();
+    let (mut in_feed, mut in_sitemap, mut minify) = (None, None, None);
+    let (mut robots, mut noindex, mut nofollow) = (None, false, false);
+    let mut template = None;
+    let (mut menus, mut weight) = (Vec::new(), 0);
+    let mut aliases = Vec::new();
+    let mut canonical = None;
+    let mut expires = None;
+    let mut updated = None;
+    let mut draft = false;
+    let mut enclosure: Option<Enclosure> = None;
+    let mut extra = Map::new();
+    let mut raw_frontmatter = String::new();
+
+    // Without frontmatter the whole file is the body; copied only in that case.
+    let (own_pairs, own_json, content) = match split_frontmatter(raw) {
+        Some(Frontmatter { pairs, json, raw: block, body }) => {
+            raw_frontmatter = block;
+            (pairs, json, body)
+        }
+        None => (Vec::new(), None, raw.to_string()),
+    };
+    let overridden = |key: &str| own_pairs.iter().any(|(own, _)| own == key);
+    let inherited: Vec<(String, String)> = json_pairs(defaults).into_iter().filter(|(k, _)| !overridden(k)).collect();
+    let inherited_json: Vec<(String, Value)> = defaults
+        .iter()
+        .filter(|(k, _)| !overridden(k))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    for (k, v) in inherited.into_iter().chain(own_pairs) {
+        let v = v.as_str();
+        match k.as_str() {
+            "title" => title = v.trim().trim_matches('"').to_string(),
+            "date" => date = v.trim().to_string(),
+            "author" | "authors" => authors = parse_list(v),
+            "excerpt" | "description" => excerpt = Some(v.trim().trim_matches('"').to_string()),
+            "menu" | "menus" => menus = parse_list(v),
+            "weight" => weight = v.trim().parse().unwrap_or(0),
+            "expires" => {
+                expires = dates::parse_date_in(v, zone);
+                if expires.is_none() {
+                    log::warn!("{}: unrecognized expires date '{}'", slug, v.trim());
                 }
             }
-            content = parts[2].trim().to_string();
+            "updated" | "modified" => {
+                updated = dates::parse_date_in(v, zone);
+                if updated.is_none() {
+                    log::warn!("{}: unrecognized {} date '{}', using file modification time", slug, k, v.trim());
+                }
+            }
+            "enclosure" | "enclosure.url" if !v.is_empty() => {
+                enclosure.get_or_insert_with(Enclosure::default).url = v.trim_matches('"').to_string();
+            }
+            "enclosure" => {}
+            "enclosure.length" => enclosure.get_or_insert_with(Enclosure::default).length = v.parse().ok(),
+            "enclosure.type" => {
+                enclosure.get_or_insert_with(Enclosure::default).mime_type = Some(v.trim_matches('"').to_string());
+            }
+            "enclosure.duration" => {
+                enclosure.get_or_insert_with(Enclosure::default).duration = Some(v.trim_matches('"').to_string());
+            }
+            "draft" => draft = v.trim().parse().unwrap_or(false),
+            "aliases" => aliases = parse_list(v),
+            "canonical" => {
+                let url = v.trim().trim_matches('"');
+                if url.starts_with("https://") || url.starts_with("http://") {
+                    canonical = Some(url.to_string());
+                } else {
+                    log::warn!("{}: canonical '{}' is not an absolute URL; ignored", slug, url);
+                }
+            }
+            "template" => template = Some(v.trim().trim_matches('"').to_string()),
+            "in_feed" => in_feed = v.trim().parse().ok(),
+            "in_sitemap" => in_sitemap = v.trim().parse().ok(),
+            "minify" => minify = v.trim().parse().ok(),
+            "robots" => robots = Some(v.trim().trim_matches('"').to_string()).filter(|r| !r.is_empty()),
+            "noindex" => noindex = v.trim().parse().unwrap_or(false),
+            "nofollow" => nofollow = v.trim().parse().unwrap_or(false),
+            "cover_image" => cover_image = Some(v.trim().trim_matches('"').to_string()),
+            "tags" => tags = parse_list(v),
+            _ => insert_extra(&mut extra, &k, v),
+        }
+    }
+    // JSON keeps its original types (numbers, arrays, objects) for templates.
+    for (k, v) in inherited_json.into_iter().chain(own_json.into_iter().flatten()) {
+        if extra.contains_key(&k) {
+            extra.insert(k, v);
+        }
+    }
+
+    let datetime = match dates::parse_date_in(&date, zone) {
+        Some(dt) => dt,
+        None => {
+            if !date.is_empty() {
+                log::warn!(
+                    "{}: unrecognized date '{}', using file modification time",
+                    slug, date
+                );
+            }
+            zone.convert(dates::from_system_time(mtime))
         }
+    };
+    if date.is_empty() {
+        date = datetime.format("%Y-%m-%d").to_string();
     }
+    let date_info = dates::DateInfo::new(&datetime, dates::DEFAULT_DATE_FORMAT, None);
+    // A checkout can leave mtimes older than a scheduled publish date.
+    let updated = updated.unwrap_or_else(|| zone.convert(dates::from_system_time(mtime)).max(datetime));
+
+    // An explicit `robots` string wins over the boolean shorthands.
+    let robots = robots.or_else(|| {
+        let directives: Vec<&str> = [(noindex, "noindex"), (nofollow, "nofollow")]
+            .into_iter()
+            .filter_map(|(set, directive)| set.then_some(directive))
+            .collect();
+        (!directives.is_empty()).then(|| directives.join(", "))
+    });
+
+    let plain_text = strip_markdown(&content);
+    let word_count = plain_text.split_whitespace().count();
+    let reading_time = word_count.div_ceil(WORDS_PER_MINUTE).max(1);
+    let excerpt = excerpt.unwrap_or_else(|| truncate_words(&plain_text, 160));
 
     Post {
         slug: slug.to_string(),
         title,
         date,
+        datetime,
+        updated,
+        draft,
+        expires,
+        date_info,
         tags,
+        authors,
+        excerpt,
+        cover_image,
         raw_content: content,
+        plain_text,
+        word_count,
+        reading_time,
         mtime,
+        raw_frontmatter,
+        source_path: PathBuf::new(),
+        subdir: String::new(),
+        lang: String::new(),
+        is_page: false,
+        collection: None,
+        menus,
+        weight,
+        canonical,
+        aliases,
+        enclosure: enclosure.filter(|e| !e.url.is_empty()),
+        extra,
+        template,
+        in_feed,
+        in_sitemap,
+        minify,
+        robots,
+    }
+}
+
+/// Shortens `text` to at most `max_chars` characters (not bytes), cutting at the
+/// last word boundary and appending an ellipsis. Text that already fits is returned as-is.
+///
+/// A single overlong word is cut mid-word rather than dropping the whole preview.
+pub fn truncate_words(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
     }
+    let cut = text.char_indices().nth(max_chars).map_or(text.len(), |(i, _)| i);
+    let head = &text[..cut];
+    let head = match head.rfind(char::is_whitespace) {
+        Some(space) if space > 0 => &head[..space],
+        _ => head,
+    };
+    format!("{}…", head.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '.' | '-')))
+}
+
+/// Turns arbitrary text into a safe, URL-friendly file stem.
+///
+/// `mode` is `build.slugify_mode`:
+/// - `"unicode"` (default): lowercase, keeping letters and digits from any script (`日本語`).
+/// - `"ascii"`: transliterate to ASCII first (`Ünïcödé` -> `unicode`, `日本` -> `ri-ben`).
+/// - `"passthrough"`: keep case and punctuation, only replacing characters that are
+///   unsafe in paths or URLs.
+///
+/// In every mode separators collapse into single dashes, so `os/linux` becomes
+/// `os-linux` and `../x` can never escape its directory.
+pub fn slugify(text: &str, mode: &str) -> String {
+    let source = match mode {
+        "ascii" => deunicode::deunicode(text),
+        _ => text.to_string(),
+    };
+    let keep = |c: char| match mode {
+        "passthrough" => !c.is_whitespace() && !c.is_control() && !"/\\?#%:*\"<>|.".contains(c),
+        "ascii" => c.is_ascii_alphanumeric(),
+        _ => c.is_alphanumeric(),
+    };
+    let chars: Box<dyn Iterator<Item = char>> = if mode == "passthrough" {
+        Box::new(source.chars())
+    } else {
+        Box::new(source.chars().flat_map(char::to_lowercase))
+    };
+
+    let mut slug = String::with_capacity(source.len());
+    for c in chars {
+        if keep(c) {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-').to_string();
+    if slug.is_empty() { "untitled".to_string() } else { slug }
+}
+
+/// A frontmatter block split off the top of a file.
+struct Frontmatter {
+    /// Flattened `(key, value)` pairs, whatever the block's syntax.
+    pairs: Vec<(String, String)>,
+    /// The parsed object for TOML and JSON blocks, so unknown keys keep their types in `extra`.
+    json: Option<Map<String, Value>>,
+    /// The block's text between the delimiters.
+    raw: String,
+    body: String,
+}
+
+/// Separates frontmatter from the body.
+///
+/// The style is picked from the opening delimiter: `---` (YAML-style key/values),
+/// `+++` (TOML), `;;;` or a leading ```` ```json ```` fence (JSON).
+fn split_frontmatter(raw: &str) -> Option<Frontmatter> {
+    let frontmatter = |pairs: Vec<(String, String)>, json: Option<Map<String, Value>>, block: &str, body: &str| Frontmatter {
+        pairs,
+        json,
+        raw: block.trim_matches('\n').to_string(),
+        body: body.trim().to_string(),
+    };
+
+    if opens_block(raw, "---") {
+        // A leading thematic break followed by another one is body text, not a block.
+        let (block, body) = split_block(raw, "---").filter(|(block, _)| looks_like_pairs(block))?;
+        return Some(frontmatter(frontmatter_pairs(block), None, block, body));
+    }
+    if opens_block(raw, "+++") {
+        let (block, body) = split_block(raw, "+++")?;
+        let Ok(toml::Value::Table(table)) = block.parse::<toml::Value>() else { return None };
+        let Value::Object(obj) = toml_to_json(toml::Value::Table(table)) else { return None };
+        return Some(frontmatter(json_pairs(&obj), Some(obj), block, body));
+    }
+
+    let (block, body) = if let Some(rest) = raw.strip_prefix(";;;") {
+        rest.split_once("\n;;;")?
+    } else if let Some(rest) = raw.strip_prefix("```json") {
+        rest.split_once("\n```")?
+    } else {
+        return None;
+    };
+    match serde_json::from_str::<Value>(block) {
+        Ok(Value::Object(obj)) => Some(frontmatter(json_pairs(&obj), Some(obj), block, body)),
+        Ok(_) | Err(_) => None,
+    }
+}
+
+/// Parses a `_defaults.toml` cascade file into the object merged under each
+/// post's frontmatter. `None` if it isn't a TOML table.
+pub fn parse_defaults(text: &str) -> Option<Map<String, Value>> {
+    let Ok(toml::Value::Table(table)) = text.parse::<toml::Value>() else { return None };
+    match toml_to_json(toml::Value::Table(table)) {
+        Value::Object(obj) => Some(obj),
+        _ => None,
+    }
+}
+
+/// TOML values as JSON; dates become their string form, as in YAML frontmatter.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect()),
+    }
+}
+
+/// Whether the first line of `raw` is exactly `delimiter`.
+fn opens_block(raw: &str, delimiter: &str) -> bool {
+    raw.lines().next().is_some_and(|line| line.trim_end() == delimiter)
+}
+
+/// Splits a block opened by `delimiter` off the top of `raw` into `(block, body)`.
+/// Both delimiters must sit on their own lines, so `---` rules in the body or
+/// inside values are left alone. `None` means the block is never closed.
+fn split_block<'a>(raw: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let mut lines = raw.split_inclusive('\n');
+    let start = lines.next()?.len();
+    let mut offset = start;
+    for line in lines {
+        if line.trim_end() == delimiter {
+            return Some((&raw[start..offset], &raw[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Whether a `---` block reads as frontmatter: every line is blank, a `#` comment,
+/// a `key: value` line or an indented/list continuation.
+fn looks_like_pairs(block: &str) -> bool {
+    block.lines().all(|line| {
+        let trimmed = line.trim();
+        trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || line.starts_with(char::is_whitespace)
+            || trimmed.starts_with("- ")
+            || is_key_line(trimmed)
+    })
+}
+
+fn is_key_line(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '"'))
+    })
+}
+
+/// True when `raw` opens a `---` block that is never closed, judged by a
+/// `key:` line right after the delimiter (a bare leading `---` rule doesn't count).
+/// [parse_frontmatter] then treats the whole file as content.
+pub fn frontmatter_is_unterminated(raw: &str) -> bool {
+    let first_key = raw.lines().skip(1).find(|l| !l.trim().is_empty()).is_some_and(|l| is_key_line(l.trim()));
+    opens_block(raw, "---") && first_key && split_block(raw, "---").is_none()
+}
+
+/// True when `raw` opens a `+++`, `;;;` or ```` ```json ```` block that can't be
+/// parsed, so the file shouldn't be published as-is.
+pub fn frontmatter_is_malformed(raw: &str) -> bool {
+    let opens = opens_block(raw, "+++") || [";;;", "```json"].iter().any(|m| raw.starts_with(m));
+    opens && split_frontmatter(raw).is_none()
+}
+
+/// Flattens a JSON frontmatter object into the same `(key, value)` pairs the
+/// YAML reader produces: arrays become quoted lists, objects become dotted keys.
+fn json_pairs(obj: &Map<String, Value>) -> Vec<(String, String)> {
+    fn scalar(v: &Value) -> String {
+        match v {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            Value::Array(items) => items
+                .iter()
+                .map(|i| format!("\"{}\"", scalar(i)))
+                .collect::<Vec<_>>()
+                .join(", "),
+            other => other.to_string(),
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (k, v) in obj {
+        match v {
+            Value::Object(inner) => {
+                pairs.push((k.clone(), String::new()));
+                pairs.extend(inner.iter().map(|(sub, v)| (format!("{}.{}", k, sub), scalar(v))));
+            }
+            _ => pairs.push((k.clone(), scalar(v))),
+        }
+    }
+    pairs
+}
+
+/// Keeps an unrecognized frontmatter key for templates (`post.extra.key`).
+/// Dotted keys from nested blocks are regrouped into an object under their parent.
+fn insert_extra(extra: &mut Map<String, Value>, key: &str, value: &str) {
+    let value = Value::String(value.trim().trim_matches('"').to_string());
+    match key.split_once('.') {
+        Some((parent, child)) => {
+            let entry = extra.entry(parent).or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            if let Value::Object(map) = entry {
+                map.insert(child.to_string(), value);
+            }
+        }
+        None => {
+            extra.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Splits the frontmatter block into `(key, value)` pairs.
+///
+/// YAML block lists (`tags:` followed by `- item` lines) are folded into an inline
+/// list value, quoting items that contain commas so [parse_list] keeps them whole.
+/// Indented `key: value` lines under an empty key become dotted keys, so a nested
+/// `enclosure:` block yields `enclosure.url`, `enclosure.type`, ...
+fn frontmatter_pairs(block: &str) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    let mut list_owner: Option<usize> = None;
+    let mut map_owner: Option<String> = None;
+
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if let (Some(idx), Some(item)) = (list_owner, trimmed.strip_prefix('-')) {
+            let item = item.trim();
+            let value = &mut pairs[idx].1;
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+            if item.contains(',') && !item.starts_with('"') && !item.starts_with('\'') {
+                value.push_str(&format!("\"{}\"", item));
+            } else {
+                value.push_str(item);
+            }
+            continue;
+        }
+
+        if let Some((k, v)) = line.split_once(':') {
+            let indented = line.starts_with(char::is_whitespace);
+            let key = match (&map_owner, indented) {
+                (Some(owner), true) => format!("{}.{}", owner, k.trim()),
+                _ => k.trim().to_string(),
+            };
+            if !indented {
+                map_owner = v.trim().is_empty().then(|| key.clone());
+            }
+            pairs.push((key, v.trim().to_string()));
+            list_owner = v.trim().is_empty().then(|| pairs.len() - 1);
+        }
+    }
+    pairs
+}
+
+/// Splits a comma-separated frontmatter value into trimmed, unquoted items.
+///
+/// Surrounding `[` `]` are accepted, so `[rust, web]`, `"rust", "web"` and `rust, web`
+/// parse the same. Commas inside quotes do not split: `"a, b", c` yields two items.
+fn parse_list(v: &str) -> Vec<String> {
+    let v = v.trim();
+    let v = v.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(v);
+
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in v.chars() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (',', None) => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items.into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Builds the pulldown-cmark option set from `[build.markdown]`.
+///
+/// Starts from [Options::all] and removes what the config turns off, so
+/// extensions without a toggle keep their previous behavior.
+pub fn markdown_options(settings: &MarkdownSettings) -> Options {
+    let mut options = Options::all();
+    let toggles = [
+        (settings.tables, Options::ENABLE_TABLES),
+        (settings.footnotes, Options::ENABLE_FOOTNOTES | Options::ENABLE_OLD_FOOTNOTES),
+        (settings.strikethrough, Options::ENABLE_STRIKETHROUGH),
+        (settings.tasklists, Options::ENABLE_TASKLISTS),
+        (settings.smart_punctuation, Options::ENABLE_SMART_PUNCTUATION),
+        (settings.heading_attributes, Options::ENABLE_HEADING_ATTRIBUTES),
+    ];
+    for (enabled, flag) in toggles {
+        if !enabled {
+            options.remove(flag);
+        }
+    }
+    options
+}
+
+/// Features a rendered body actually uses, so pages only load the assets they need.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PageFeatures {
+    /// A code block other than a mermaid diagram.
+    pub has_code: bool,
+    /// Inline (`$...$`) or display (`$$...$$`) math.
+    pub has_math: bool,
+    /// A ```` ```mermaid ```` block passed through by `build.mermaid`.
+    pub has_mermaid: bool,
 }
 
 /// Renders Markdown string to HTML using pulldown-cmark and syntect for code highlighting.
-pub fn render_markdown(
-    md: &str,
-    use_syntect: bool,
-    ps: &SyntaxSet,
-    theme: &Theme,
-) -> String {
-    let options = Options::all();
-    let parser = Parser::new_ext(md, options);
+///
+/// Per-language themes from `[build.syntax_themes]` override the main theme for code
+/// blocks whose info string starts with a mapped language token.
+pub fn render_markdown(md: &str, options: Options, render: &RenderOptions) -> String {
+    render_markdown_with_features(md, options, render).0
+}
+
+/// [render_markdown], also reporting which [PageFeatures] the body uses.
+pub fn render_markdown_with_features(md: &str, options: Options, render: &RenderOptions) -> (String, PageFeatures) {
+    // Merged text keeps `:shortcode:` emoji names in one event.
+    let parser = TextMergeStream::new(Parser::new_ext(md, options));
+    let mut features = PageFeatures::default();
 
     let mut events = Vec::new();
     let mut temp_code = String::new();
     let mut in_code_block = false;
+    // Any code block, highlighted or not; its text is never touched.
+    let mut in_any_code = false;
     let mut current_lang = String::new();
+    // Indices into `events` of the open lists/items, for task-list class rewriting.
+    let (mut open_lists, mut open_items): (Vec<usize>, Vec<usize>) = (Vec::new(), Vec::new());
+    let mut task_lists = Vec::new();
 
     for event in parser {
+        match &event {
+            Event::InlineMath(_) | Event::DisplayMath(_) => features.has_math = true,
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(label))) if render.mermaid && info_token(label) == "mermaid" => {
+                features.has_mermaid = true;
+                in_any_code = true;
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                features.has_code = true;
+                in_any_code = true;
+            }
+            Event::End(TagEnd::CodeBlock) => in_any_code = false,
+            _ => {}
+        }
         match event {
-            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(label))) if use_syntect => {
+            Event::Start(Tag::List(_)) => {
+                open_lists.push(events.len());
+                events.push(event);
+            }
+            Event::End(TagEnd::List(_)) => {
+                open_lists.pop();
+                events.push(event);
+            }
+            Event::Start(Tag::Item) => {
+                open_items.push(events.len());
+                events.push(event);
+            }
+            Event::End(TagEnd::Item) => {
+                open_items.pop();
+                events.push(event);
+            }
+            Event::TaskListMarker(checked) => {
+                // Static pages can't persist a toggle, so the checkbox is read-only.
+                if let Some(&item) = open_items.last() {
+                    events[item] = Event::Html(r#"<li class="task-list-item">"#.into());
+                }
+                if let Some(&list) = open_lists.last() {
+                    task_lists.push(list);
+                }
+                let checked = if checked { " checked" } else { "" };
+                events.push(Event::Html(
+                    format!(r#"<input type="checkbox" class="task-list-item-checkbox" disabled{}/>"#, checked).into(),
+                ));
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(label)))
+                if render.highlighter.is_some() || (render.mermaid && info_token(&label) == "mermaid") =>
+            {
                 in_code_block = true;
                 current_lang = label.to_string();
                 temp_code.clear();
             }
             Event::End(TagEnd::CodeBlock) if in_code_block => {
                 in_code_block = false;
-                let syntax = ps
-                    .find_syntax_by_token(&current_lang)
-                    .unwrap_or_else(|| ps.find_syntax_plain_text());
-
-                let highlighted = highlighted_html_for_string(&temp_code, ps, syntax, theme)
-                    .unwrap_or_else(|_| {
-                        format!("<pre><code>{}</code></pre>", temp_code)
-                    });
+                let labeled = info_token(&current_lang);
+                let token = if labeled.is_empty() { render.default_lang.unwrap_or("") } else { labeled };
+                if render.mermaid && token == "mermaid" {
+                    // Escaped but otherwise verbatim: mermaid parses the text content.
+                    let diagram = format!("<pre class=\"mermaid\">{}</pre>\n", escape_attr(&temp_code));
+                    events.push(Event::Html(diagram.into()));
+                    continue;
+                }
+                let Some(h) = render.highlighter else { continue };
+                let ps = &h.syntax_set;
+                // Unknown languages (`mermaid`, `text`, ...) stay unstyled with their
+                // class intact so client-side tools can still find them.
+                // An unknown default language falls back to plain text, not an unstyled block.
+                let syntax = if token.is_empty() {
+                    Some(ps.find_syntax_plain_text())
+                } else if labeled.is_empty() {
+                    Some(ps.find_syntax_by_token(token).unwrap_or_else(|| ps.find_syntax_plain_text()))
+                } else {
+                    ps.find_syntax_by_token(token)
+                };
+                let highlighted = syntax.and_then(|syntax| {
+                    if render.css_classes {
+                        classed_html_for_string(&temp_code, ps, syntax)
+                    } else {
+                        let theme = h.lang_themes.get(&token.to_lowercase()).unwrap_or(&h.theme);
+                        highlighted_html_for_string(&temp_code, ps, syntax, theme).ok()
+                    }
+                });
+                let highlighted = highlighted.unwrap_or_else(|| plain_code_block(&temp_code, token));
 
                 events.push(Event::Html(highlighted.into()));
             }
             Event::Text(text) => {
                 if in_code_block {
                     temp_code.push_str(&text);
+                } else if render.emoji && !in_any_code && text.contains(':') {
+                    events.push(Event::Text(replace_emoji(&text).into()));
                 } else {
                     events.push(Event::Text(text));
                 }
@@ -112,9 +690,78 @@ pub fn render_markdown(
         }
     }
 
+    for idx in task_lists {
+        if let Event::Start(Tag::List(None)) = events[idx] {
+            events[idx] = Event::Html("<ul class=\"contains-task-list\">\n".into());
+        }
+    }
+
     let mut html_output = String::new();
     html::push_html(&mut html_output, events.into_iter());
-    html_output
+    (html_output, features)
+}
+
+/// Replaces gemoji-style `:shortcode:` names (`:rocket:`) with their emoji. Unknown
+/// names are left as written, so `:-)` or `std::io` come through unchanged.
+fn replace_emoji(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(':') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let emoji = after.find(':').and_then(|close| {
+            let name = &after[..close];
+            let valid = !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'));
+            valid.then(|| emojis::get_by_shortcode(name)).flatten().map(|e| (e, close))
+        });
+        match emoji {
+            Some((emoji, close)) => {
+                out.push_str(emoji.as_str());
+                rest = &after[close + 1..];
+            }
+            None => {
+                // The closing colon may still open the next shortcode.
+                out.push(':');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The language token of a fence info string, which may carry extras:
+/// "rust,ignore" or "sh {.class}".
+fn info_token(info: &str) -> &str {
+    info.split(|c: char| c == ',' || c == '{' || c.is_whitespace())
+        .next()
+        .unwrap_or_default()
+}
+
+/// Highlights `code` with `class` attributes for the stylesheet written by
+/// [`syntax_css`], instead of inline styles.
+fn classed_html_for_string(code: &str, ps: &SyntaxSet, syntax: &SyntaxReference) -> Option<String> {
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, ps, ClassStyle::Spaced);
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line).ok()?;
+    }
+    Some(format!("<pre class=\"code\"><code>{}</code></pre>", generator.finalize()))
+}
+
+/// An unhighlighted block, marked up the way pulldown-cmark would emit it.
+fn plain_code_block(code: &str, lang: &str) -> String {
+    let class = if lang.is_empty() {
+        String::new()
+    } else {
+        format!(r#" class="language-{}""#, escape_attr(lang))
+    };
+    format!("<pre><code{}>{}</code></pre>\n", class, escape_attr(code))
+}
+
+/// Stylesheet for class-based highlighting, generated from the configured theme.
+pub fn syntax_css(highlighter: &Highlighter) -> Option<String> {
+    css_for_theme_with_class_style(&highlighter.theme, ClassStyle::Spaced).ok()
 }
 
 /// Strips Markdown syntax to produce clean plain text for search indexing.
@@ -131,5 +778,142 @@ pub fn strip_markdown(md: &str) -> String {
             _ => {}
         }
     }
-    plain_text.trim().to_string()
-}
\ No newline at end of file
+    // Trimmed in place: multi-megabyte documents shouldn't be copied again here.
+    plain_text.truncate(plain_text.trim_end().len());
+    let leading = plain_text.len() - plain_text.trim_start().len();
+    plain_text.drain(..leading);
+    plain_text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain() -> RenderOptions<'static> {
+        RenderOptions { highlighter: None, css_classes: false, mermaid: false, emoji: false, default_lang: None }
+    }
+
+    fn render(md: &str) -> String {
+        render_markdown(md, markdown_options(&MarkdownSettings::default()), &plain())
+    }
+
+    #[test]
+    fn parse_list_accepts_brackets_and_quoted_commas() {
+        assert_eq!(parse_list("[rust, web]"), ["rust", "web"]);
+        assert_eq!(parse_list(r#""rust", 'web'"#), ["rust", "web"]);
+        assert_eq!(parse_list(r#""a, b", c"#), ["a, b", "c"]);
+        assert!(parse_list("[]").is_empty());
+    }
+
+    #[test]
+    fn parse_list_reads_yaml_block_lists() {
+        let pairs = frontmatter_pairs("tags:\n  - rust\n  - a, b\n  - \"c, d\"\ntitle: x\n");
+        let tags = &pairs.iter().find(|(k, _)| k == "tags").unwrap().1;
+        assert_eq!(parse_list(tags), ["rust", "a, b", "c, d"]);
+    }
+
+    #[test]
+    fn slugified_tags_stay_inside_tags_dir() {
+        for mode in ["unicode", "ascii", "passthrough"] {
+            for tag in ["os/linux", "../../etc", "a\\b", "/"] {
+                let slug = slugify(tag, mode);
+                let route = std::path::Path::new("tags").join(&slug);
+                let parts: Vec<_> = route.components().collect();
+                assert_eq!(parts.len(), 2, "{} ({}) -> {}", tag, mode, slug);
+                assert!(matches!(parts[1], std::path::Component::Normal(_)), "{} ({}) -> {}", tag, mode, slug);
+            }
+        }
+    }
+
+    #[test]
+    fn task_lists_render_read_only_checkboxes() {
+        let html = render("- [x] done\n- [ ] todo\n");
+        assert!(html.contains(r#"<ul class="contains-task-list">"#), "{}", html);
+        assert!(html.contains(r#"class="task-list-item-checkbox" disabled checked/>"#), "{}", html);
+        assert!(html.contains(r#"class="task-list-item-checkbox" disabled/>"#), "{}", html);
+        assert_eq!(html.matches(r#"<li class="task-list-item">"#).count(), 2, "{}", html);
+    }
+
+    #[test]
+    fn unterminated_frontmatter_is_detected() {
+        let raw = "---\ntitle: Hello\ndate: 2024-01-01\n\nBody text.\n";
+        assert!(frontmatter_is_unterminated(raw));
+        assert!(split_frontmatter(raw).is_none());
+    }
+
+    #[test]
+    fn empty_frontmatter_block_is_accepted() {
+        let fm = split_frontmatter("---\n---\nBody text.\n").expect("empty block");
+        assert!(fm.pairs.is_empty());
+        assert_eq!(fm.body, "Body text.");
+        assert!(!frontmatter_is_unterminated("---\n---\nBody text.\n"));
+    }
+
+    #[test]
+    fn horizontal_rules_in_body_stay_in_body() {
+        let raw = "---\ntitle: Hello\n---\nIntro\n\n---\n\nMore\n\n---\n";
+        let fm = split_frontmatter(raw).expect("frontmatter");
+        assert_eq!(fm.pairs, [("title".to_string(), "Hello".to_string())]);
+        assert_eq!(fm.body, "Intro\n\n---\n\nMore\n\n---");
+    }
+
+    #[test]
+    fn leading_thematic_break_is_body() {
+        let raw = "---\nJust some prose, no keys here.\n---\nMore prose.\n";
+        assert!(split_frontmatter(raw).is_none());
+        assert!(!frontmatter_is_unterminated(raw));
+    }
+
+    #[test]
+    fn setext_underline_is_not_a_closing_delimiter() {
+        let raw = "---\n\nA Heading\n---\n\nText.\n";
+        assert!(split_frontmatter(raw).is_none());
+        assert!(!frontmatter_is_unterminated(raw));
+    }
+
+    #[test]
+    fn yaml_block_with_list_items_is_frontmatter() {
+        let raw = "---\ntitle: Hello\ntags:\n  - rust\n  - web\n---\nBody.\n";
+        let fm = split_frontmatter(raw).expect("frontmatter");
+        let tags = &fm.pairs.iter().find(|(k, _)| k == "tags").unwrap().1;
+        assert_eq!(parse_list(tags), ["rust", "web"]);
+        assert_eq!(fm.body, "Body.");
+    }
+
+    #[test]
+    fn smart_punctuation_never_touches_code() {
+        let settings = MarkdownSettings { smart_punctuation: true, ..Default::default() };
+        let md = "Say \"hello\", then run `a--b`.\n\n```\n\"quoted\" -- text\n```\n";
+        let html = render_markdown(md, markdown_options(&settings), &plain());
+        assert!(html.contains("\u{201c}hello\u{201d}"), "{}", html);
+        assert!(html.contains("<code>a--b</code>"), "{}", html);
+        assert!(html.contains("\"quoted\" -- text"), "{}", html);
+    }
+
+    #[test]
+    fn smart_punctuation_off_keeps_flags_and_quotes() {
+        let settings = MarkdownSettings { smart_punctuation: false, ..Default::default() };
+        let html = render_markdown("Say \"hello\" to --flag.", markdown_options(&settings), &plain());
+        assert!(html.contains("\"hello\""), "{}", html);
+        assert!(html.contains("--flag"), "{}", html);
+    }
+
+    #[test]
+    fn unlabeled_code_uses_default_language() {
+        let highlighter = Highlighter {
+            syntax_set: SyntaxSet::load_defaults_newlines(),
+            theme: syntect::highlighting::ThemeSet::load_defaults().themes["base16-ocean.dark"].clone(),
+            lang_themes: Default::default(),
+        };
+        let md = "```\nfn main() {}\n```\n";
+        let options = markdown_options(&MarkdownSettings::default());
+        let mut render = RenderOptions { highlighter: Some(&highlighter), css_classes: true, ..plain() };
+
+        let unlabeled = render_markdown(md, options, &render);
+        assert!(!unlabeled.contains("source rust"), "{}", unlabeled);
+
+        render.default_lang = Some("rust");
+        let defaulted = render_markdown(md, options, &render);
+        assert!(defaulted.contains("source rust"), "{}", defaulted);
+    }
+}
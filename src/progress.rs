@@ -0,0 +1,61 @@
+//! Progress feedback for the long parallel stages (rendering, image conversion).
+//!
+//! On an interactive terminal this is an `indicatif` bar. Otherwise (piped output,
+//! CI) it falls back to a log line roughly every 10%, and under `--quiet` it is silent.
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Stages smaller than this finish too quickly to need feedback.
+const MIN_ITEMS: usize = 50;
+
+pub struct Progress {
+    label: &'static str,
+    total: usize,
+    done: AtomicUsize,
+    bar: Option<ProgressBar>,
+    /// Textual fallback is used when there's no bar but info output is enabled.
+    text: bool,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: usize) -> Self {
+        let visible = total >= MIN_ITEMS && log::log_enabled!(log::Level::Info);
+        let interactive = visible && std::io::stderr().is_terminal();
+        let bar = interactive.then(|| {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template("  {msg:>10} [{bar:30}] {pos}/{len}")
+                    .unwrap_or_else(|_| ProgressStyle::default_bar())
+                    .progress_chars("=> "),
+            );
+            bar.set_message(label);
+            bar
+        });
+        Self { label, total, done: AtomicUsize::new(0), bar, text: visible && !interactive }
+    }
+
+    /// Records one finished item. Safe to call from Rayon workers.
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        } else if self.text {
+            let step = (self.total / 10).max(1);
+            if done.is_multiple_of(step) || done == self.total {
+                log::info!("  {} {}/{}", self.label, done, self.total);
+            }
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
@@ -1,31 +1,178 @@
-use crate::models::Post;
+use crate::models::{Enclosure, Post};
+use crate::parser;
 use crate::config::Config;
-use rss::{ChannelBuilder, ItemBuilder, GuidBuilder};
-use chrono::{Utc, NaiveDate, TimeZone};
+use rss::{CategoryBuilder, ChannelBuilder, EnclosureBuilder, ItemBuilder, GuidBuilder};
+use rss::extension::dublincore::DublinCoreExtensionBuilder;
+use rss::extension::itunes::{ITunesCategoryBuilder, ITunesChannelExtensionBuilder, ITunesItemExtensionBuilder};
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
 
-fn format_rss_date(date_str: &str) -> String {
-    NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
-        .map(|d| Utc.from_utc_datetime(&d.and_hms_opt(0,0,0).unwrap()).to_rfc2822())
-        .unwrap_or_else(|_| Utc::now().to_rfc2822())
+/// Tera function `feed_links(lang=lang)`: `<link rel="alternate">` tags for every
+/// enabled feed, with absolute URLs, for themes to drop into `<head>`. `lang` picks
+/// the language subtree and defaults to `site.default_language`.
+pub fn feed_links_function(config: &Config) -> impl Fn(&HashMap<String, Value>) -> tera::Result<Value> + Send + Sync + use<> {
+    let config = config.clone();
+    move |args| {
+        let lang = args.get("lang").and_then(|v| v.as_str()).unwrap_or(&config.site.default_language);
+        let feed_url = |file: &str| {
+            if lang == config.site.default_language {
+                config.absolute_url(file)
+            } else {
+                config.absolute_url(&format!("{}/{}", lang, file))
+            }
+        };
+
+        let mut links = Vec::new();
+        if config.site.generate_rss {
+            links.push(format!(
+                r#"<link rel="alternate" type="application/rss+xml" title="{}" href="{}">"#,
+                crate::seo::escape_attr(&config.site.title),
+                crate::seo::escape_attr(&feed_url("rss.xml"))
+            ));
+        }
+        Ok(Value::String(links.join("\n")))
+    }
+}
+
+/// Display names for a post's authors, using `data/authors.toml` names when present
+/// and falling back to `site.author`.
+fn author_names(post: &Post, config: &Config, data: &Value) -> Vec<String> {
+    if post.authors.is_empty() {
+        return if config.site.author.is_empty() { Vec::new() } else { vec![config.site.author.clone()] };
+    }
+    post.authors.iter().map(|key| {
+        data.get("authors")
+            .and_then(|a| a.get(key))
+            .and_then(|a| a.get("name"))
+            .and_then(|n| n.as_str())
+            .unwrap_or(key)
+            .to_string()
+    }).collect()
+}
+
+/// Best-effort MIME type for common podcast/video formats.
+fn guess_mime(url: &str) -> &'static str {
+    let ext = Path::new(url).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match ext.as_str() {
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "wav" => "audio/wav",
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Builds an `<enclosure>`, stat-ing local files in the output for a missing length.
+fn build_enclosure(enc: &Enclosure, config: &Config) -> rss::Enclosure {
+    let is_local = !enc.url.contains("://") && !enc.url.starts_with("//");
+    let length = enc.length.or_else(|| {
+        is_local
+            .then(|| fs::metadata(config.output_dir.join(enc.url.trim_start_matches('/'))).ok())
+            .flatten()
+            .map(|m| m.len())
+    });
+
+    EnclosureBuilder::default()
+        .url(config.absolute_url(&enc.url))
+        .length(length.unwrap_or(0).to_string())
+        .mime_type(enc.mime_type.clone().unwrap_or_else(|| guess_mime(&enc.url).to_string()))
+        .build()
 }
 
-pub fn generate_rss(posts: &[Post], config: &Config) -> String {
-    let items: Vec<_> = posts.iter().take(15).map(|p| {
-        let link = format!("{}/{}.html", config.base_url, p.slug);
+/// Drops characters that XML 1.0 forbids outright (most C0 controls, e.g. a stray
+/// form feed pasted from a PDF). The `rss` writer escapes `&`, `<` and `>` itself,
+/// but no escaping can make these valid, and one of them breaks the whole feed.
+fn xml_safe(text: &str) -> String {
+    text.chars()
+        .filter(|&c| matches!(c, '\t' | '\n' | '\r') || (c >= ' ' && c != '\u{FFFE}' && c != '\u{FFFF}'))
+        .collect()
+}
+
+pub fn generate_rss(posts: &[&Post], config: &Config, data: &Value) -> String {
+    let limit = match config.site.feed_item_count {
+        0 => posts.len(),
+        n => n,
+    };
+    // The newest item's edit date rather than the clock, so an unchanged site
+    // produces a byte-identical feed.
+    let last_build = posts.iter().take(limit).map(|p| p.updated.max(p.datetime)).max();
+    let items: Vec<_> = posts.iter().take(limit).map(|p| {
+        let link = config.permalink_for(p);
+        let creators = author_names(p, config, data);
+        let dc = (!creators.is_empty()).then(|| {
+            DublinCoreExtensionBuilder::default()
+                .creators(creators.iter().map(|c| xml_safe(c)).collect::<Vec<_>>())
+                .build()
+        });
+        // One <category> per tag, in frontmatter order so output stays deterministic
+        let categories: Vec<_> = p.tags.iter()
+            .map(|t| CategoryBuilder::default().name(xml_safe(t)).build())
+            .collect();
+        let enclosure = p.enclosure.as_ref().map(|e| build_enclosure(e, config));
+        let itunes = config.podcast.as_ref().map(|podcast| {
+            ITunesItemExtensionBuilder::default()
+                .duration(p.enclosure.as_ref().and_then(|e| e.duration.clone()))
+                .explicit(Some(if podcast.explicit { "true" } else { "false" }.to_string()))
+                .build()
+        });
         ItemBuilder::default()
-            .title(Some(p.title.clone()))
+            .title(Some(xml_safe(&p.title)))
             .link(Some(link.clone()))
             .guid(Some(GuidBuilder::default().value(link).build()))
-            .description(Some(p.raw_content.chars().take(500).collect()))
-            .pub_date(Some(format_rss_date(&p.date)))
+            .description(Some(xml_safe(&parser::truncate_words(&p.plain_text, 500))))
+            .pub_date(Some(p.datetime.to_rfc2822()))
+            .categories(categories)
+            .enclosure(enclosure)
+            .itunes_ext(itunes)
+            .dublin_core_ext(dc)
             .build()
     }).collect();
 
+    let itunes = config.podcast.as_ref().map(|podcast| {
+        ITunesChannelExtensionBuilder::default()
+            .author(podcast.author.clone().or_else(|| Some(config.site.author.clone())))
+            .explicit(Some(if podcast.explicit { "true" } else { "false" }.to_string()))
+            .image(podcast.image.as_ref().map(|i| config.absolute_url(i)))
+            .summary(podcast.summary.clone())
+            .categories(
+                podcast.category.iter()
+                    .map(|c| ITunesCategoryBuilder::default().text(c.clone()).build())
+                    .collect::<Vec<_>>(),
+            )
+            .build()
+    });
+
     ChannelBuilder::default()
-        .title("Novos")
-        .link(config.base_url.clone())
-        .description("Build at the speed of thought.")
+        .title(xml_safe(&config.site.title))
+        .link(config.content_url("/"))
+        .description(xml_safe(&config.site.description))
+        .language(Some(config.language().to_string()))
+        .last_build_date(last_build.map(|d| d.to_rfc2822()))
+        .generator(Some(format!("novos v{}", env!("CARGO_PKG_VERSION"))))
         .items(items)
+        .itunes_ext(itunes)
         .build()
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates;
+    use std::time::SystemTime;
+
+    #[test]
+    fn escaped_title_yields_well_formed_feed() {
+        let config: Config = toml::from_str("").unwrap();
+        let zone = dates::Zone::from_config(None).unwrap();
+        let raw = "---\ntitle: Rust & Cargo <3\ndate: 2024-01-01\ntags: a&b\n---\nBody with a stray \u{c} form feed.\n";
+        let post = parser::parse_frontmatter(raw, "rust-cargo", SystemTime::now(), &zone);
+
+        let xml = generate_rss(&[&post], &config, &Value::Null);
+        let channel = rss::Channel::read_from(xml.as_bytes()).expect("well-formed feed");
+        assert_eq!(channel.items()[0].title(), Some("Rust & Cargo <3"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Social preview metadata (Open Graph & Twitter Cards).
+//!
+//! Builds a `seo` context object for every rendered page so templates can drop
+//! `{{ seo.html }}` into their `<head>` instead of hand-writing meta tags.
+
+use crate::{config::Config, models::Post};
+use serde::Serialize;
+
+/// Fully-resolved social metadata for a single page.
+#[derive(Serialize)]
+pub struct SeoMeta {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    /// Absolute image URL, falling back to `site.default_image`.
+    pub image: Option<String>,
+    pub site_name: String,
+    /// `article` for posts, `website` for everything else.
+    pub kind: String,
+    /// RFC 3339 publish date (posts only).
+    pub published: Option<String>,
+    /// RFC 3339 last-edit date (posts only), from `updated` or the file's mtime.
+    pub modified: Option<String>,
+    /// Pre-rendered `<meta>` tags for direct inclusion in templates.
+    pub html: String,
+}
+
+impl SeoMeta {
+    /// Metadata for an individual post.
+    pub fn for_post(post: &Post, config: &Config) -> Self {
+        let image = post.cover_image.as_ref().or(config.site.default_image.as_ref());
+        let mut seo = Self::build(
+            &post.title,
+            &post.excerpt,
+            config.permalink_for(post),
+            image.map(|i| config.absolute_url(i)),
+            "article",
+            config,
+        );
+
+        let (published, modified) = (post.datetime.to_rfc3339(), post.updated.to_rfc3339());
+        let structured = serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "BlogPosting",
+            "headline": post.title,
+            "url": seo.url,
+            "image": seo.image,
+            "datePublished": published,
+            "dateModified": modified,
+        });
+        // `</` inside a string would end the script element early.
+        let structured = structured.to_string().replace("</", "<\\/");
+        seo.html.push_str(&format!(
+            "\n{}\n{}\n<script type=\"application/ld+json\">{}</script>",
+            meta("property", "article:published_time", &published),
+            meta("property", "article:modified_time", &modified),
+            structured
+        ));
+        seo.published = Some(published);
+        seo.modified = Some(modified);
+        seo
+    }
+
+    /// Metadata for site-level pages (index, pagination, taxonomies).
+    pub fn for_site(config: &Config) -> Self {
+        Self::build(
+            &config.site.title,
+            &config.site.description,
+            config.content_url(""),
+            config.site.default_image.as_ref().map(|i| config.absolute_url(i)),
+            "website",
+            config,
+        )
+    }
+
+    fn build(title: &str, description: &str, url: String, image: Option<String>, kind: &str, config: &Config) -> Self {
+        let mut tags = vec![
+            meta("property", "og:type", kind),
+            meta("property", "og:title", title),
+            meta("property", "og:description", description),
+            meta("property", "og:url", &url),
+            meta("property", "og:site_name", &config.site.title),
+            meta("name", "twitter:title", title),
+            meta("name", "twitter:description", description),
+        ];
+
+        if let Some(img) = &image {
+            tags.push(meta("property", "og:image", img));
+            tags.push(meta("name", "twitter:card", "summary_large_image"));
+            tags.push(meta("name", "twitter:image", img));
+        } else {
+            tags.push(meta("name", "twitter:card", "summary"));
+        }
+
+        SeoMeta {
+            title: title.to_string(),
+            description: description.to_string(),
+            url,
+            image,
+            site_name: config.site.title.clone(),
+            kind: kind.to_string(),
+            published: None,
+            modified: None,
+            html: tags.join("\n"),
+        }
+    }
+}
+
+fn meta(attr: &str, key: &str, value: &str) -> String {
+    format!(r#"<meta {}="{}" content="{}">"#, attr, key, escape_attr(value))
+}
+
+/// Minimal attribute escaping; values come from user frontmatter.
+pub fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
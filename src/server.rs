@@ -1,100 +1,127 @@
 //! High-performance development server for `novos`.
-use crate::build::perform_build;
-use crate::config::Config;
+use crate::build::{perform_build, BuildOptions};
+use crate::cache::BuildCache;
+use crate::config::{Config, ServerSettings, UrlStyle};
 use anyhow::Result;
+use console::style;
 use axum::{
-    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Request, State},
+    middleware::map_request_with_state,
     routing::get,
     Router,
 };
-use notify::{PollWatcher, Config as WatcherConfig, RecursiveMode, Watcher};
-use std::{path::Path, sync::{Arc, Mutex}, time::{SystemTime, Duration}};
-use tokio::sync::{broadcast, mpsc};
+use crate::watch::{load_ignore_list, spawn_rebuild_worker, start_watcher};
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex}, time::{Duration, SystemTime}};
+use tokio::sync::broadcast;
+use axum_server::tls_rustls::RustlsConfig;
 use tower_http::services::ServeDir;
 
+/// Certificate for `novos serve --tls`.
+pub enum Tls {
+    /// A throwaway certificate for `localhost`, generated at startup.
+    SelfSigned,
+    /// PEM-encoded certificate chain and private key.
+    Files { cert: PathBuf, key: PathBuf },
+}
+
+/// Builds, watches and serves the site using `config.server` (already merged with CLI flags).
 pub async fn serve(
     config: Config,
     last_run: Arc<Mutex<SystemTime>>,
-    port: u16,
-    verbose: bool,
+    tls: Option<Tls>,
 ) -> Result<()> {
-    // 1. Build initial ignore list as owned Strings
-    let mut ignore_list = vec![
-        ".git".to_string(), 
-        "target".to_string(), 
-        "#".to_string(), 
-        ".swp".to_string()
-    ];
-    
-    if let Ok(gc) = tokio::fs::read_to_string(".gitignore").await {
-        for line in gc.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
-            ignore_list.push(line.to_string()); // Convert to owned String
-        }
-    }
+    let ServerSettings { port, host, open, poll_interval, live_reload_path: live_path } = config.server.clone();
+
+    // 1. Build initial ignore list
+    let ignore_list = load_ignore_list(&config).await;
 
-    // 2. Initial build
-    perform_build(&config, Arc::clone(&last_run), verbose, true)?;
+    // 2. Initial build (the cache keeps syntect assets loaded across rebuilds)
+    let cache = Arc::new(BuildCache::default());
+    let opts = BuildOptions { is_dev: true, live_reload_path: live_path.clone(), ..Default::default() };
+    perform_build(&config, Arc::clone(&last_run), &cache, &opts)?;
 
     let (tx, _rx) = broadcast::channel::<()>(16);
-    let (event_tx, mut event_rx) = mpsc::channel::<()>(100);
 
     // 3. Async Build Worker
-    let tx_worker = tx.clone();
-    let config_worker = config.clone();
-    let lr_worker = Arc::clone(&last_run);
-    tokio::spawn(async move {
-        while let Some(_) = event_rx.recv().await {
-            tokio::time::sleep(Duration::from_millis(150)).await;
-            while event_rx.try_recv().is_ok() {}
-            if verbose { println!("\x1b[32m[novos] Change detected, rebuilding...\x1b[0m"); }
-            if perform_build(&config_worker, Arc::clone(&lr_worker), verbose, true).is_ok() {
-                let _ = tx_worker.send(());
-            }
-        }
-    });
+    let event_tx = spawn_rebuild_worker(config.clone(), Arc::clone(&last_run), cache, opts, Some(tx.clone()));
 
-    // 4. The PollWatcher (Panic-proof for FreeBSD)
-    let watcher_tx = event_tx.clone();
-    let watch_config = WatcherConfig::default().with_poll_interval(Duration::from_millis(200));
-    
-    let mut watcher = PollWatcher::new(move |res: notify::Result<notify::Event>| {
-        if let Ok(event) = res {
-            let is_valid = event.paths.iter().any(|p| {
-                let s = p.to_string_lossy();
-                let name = p.file_name().unwrap_or_default().to_string_lossy();
-                
-                let is_ignored = ignore_list.iter().any(|ig| s.contains(ig)) 
-                                || name.starts_with('.') 
-                                || name.starts_with('#') 
-                                || name.ends_with('~');
-                !is_ignored
-            });
-
-            if is_valid && (event.kind.is_modify() || event.kind.is_create()) {
-                let _ = watcher_tx.try_send(());
-            }
-        }
-    }, watch_config)?;
-
-    watcher.watch(Path::new("."), RecursiveMode::Recursive)?;
+    // 4. The PollWatcher
+    let _watcher = start_watcher(ignore_list, event_tx, Duration::from_millis(poll_interval))?;
 
     // 5. Axum Server
     let app = Router::new()
-        .route("/novos/live", get(move |ws: WebSocketUpgrade| {
+        .route(&live_path, get(move |ws: WebSocketUpgrade| {
             let rx = tx.subscribe();
             async move { ws.on_upgrade(|socket| handle_socket(socket, rx)) }
         }))
         .fallback_service(ServeDir::new(&config.output_dir));
+    let app = if config.build.url_style == UrlStyle::Extensionless {
+        app.layer(map_request_with_state(config.output_dir.clone(), resolve_extensionless))
+    } else {
+        app
+    };
 
-    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
-    println!("\x1b[33m novos thinking at http://localhost:{}\x1b[0m", port);
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
+    let addr = tokio::net::lookup_host((host.as_str(), port))
+        .await?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("cannot resolve server host '{}'", host))?;
+    // Wildcard and loopback binds are reachable as localhost.
+    let display_host = if addr.ip().is_unspecified() || addr.ip().is_loopback() { "localhost" } else { host.as_str() };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    let url = format!("{}://{}:{}", scheme, display_host, port);
+    log::info!("{}", style(format!(" novos thinking at {}", url)).yellow());
+    if open && let Err(e) = open::that_detached(&url) {
+        log::warn!("could not open a browser: {}", e);
+    }
+
+    match tls {
+        Some(tls) => {
+            // The live-reload client switches to wss:// on https:// pages by itself.
+            let rustls = rustls_config(tls).await?;
+            axum_server::bind_rustls(addr, rustls).serve(app.into_make_service()).await?;
+        }
+        None => {
+            axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
+        }
+    }
 
     Ok(())
 }
 
+async fn rustls_config(tls: Tls) -> Result<RustlsConfig> {
+    match tls {
+        Tls::Files { cert, key } => RustlsConfig::from_pem_file(&cert, &key).await.map_err(|e| {
+            anyhow::anyhow!("could not load TLS certificate {} / key {}: {}", cert.display(), key.display(), e)
+        }),
+        Tls::SelfSigned => {
+            let names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+            let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(names)?;
+            log::warn!("using a self-signed certificate; your browser will ask you to trust it");
+            Ok(RustlsConfig::from_pem(cert.pem().into_bytes(), key_pair.serialize_pem().into_bytes()).await?)
+        }
+    }
+}
+
+/// Serves `/about` from `about.html`, as hosts do for `url_style = "extensionless"`.
+async fn resolve_extensionless(State(root): State<PathBuf>, mut req: Request) -> Request {
+    let path = req.uri().path();
+    let rel = path.trim_start_matches('/');
+    let is_bare = !rel.is_empty() && !rel.ends_with('/') && Path::new(rel).extension().is_none();
+    if is_bare && root.join(format!("{}.html", rel)).is_file() {
+        let mut uri = format!("{}.html", path);
+        if let Some(query) = req.uri().query() {
+            uri.push('?');
+            uri.push_str(query);
+        }
+        if let Ok(uri) = uri.parse() {
+            *req.uri_mut() = uri;
+        }
+    }
+    req
+}
+
 async fn handle_socket(mut socket: WebSocket, mut rx: broadcast::Receiver<()>) {
-    while let Ok(_) = rx.recv().await {
+    while rx.recv().await.is_ok() {
         if socket.send(Message::Text("reload".into())).await.is_err() { break; }
     }
 }
\ No newline at end of file
@@ -0,0 +1,290 @@
+//! Shortcode and include expansion for Markdown content.
+//!
+//! Runs over the raw Markdown before it is rendered:
+//! - `{{< name key="value" >}}` renders `shortcodes/name.html` with the arguments in context.
+//! - `{% include "file.html" %}` splices in a file from the includes directory.
+//!
+//! - `{{< include_code file="src/main.rs" region="setup" >}}` embeds a source file
+//!   (or a `lines="10-20"` range, or a region between `ANCHOR: setup` and
+//!   `ANCHOR_END: setup` comments) as a fenced block, highlighted like any other.
+//!
+//! Both look in the project's `includes_dir` first and fall back to the active
+//! theme's `includes/`, mirroring the theme-override rules used elsewhere. Both
+//! directories are read into memory once per build, so a footer included on every
+//! page costs one read.
+//!
+//! `[build.replacements]` macros (`{{VERSION}}` -> `2.1.0`) are substituted in the
+//! same pass, after includes are spliced in.
+//!
+//! Fenced code blocks are left untouched so docs can show the syntax literally.
+
+use crate::config::Config;
+use regex::{Captures, Regex};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Per-build resolver over the preloaded includes.
+pub struct Shortcodes {
+    project_dir: PathBuf,
+    /// Path relative to the includes root (forward slashes) -> file contents.
+    /// Project files shadow theme files with the same path.
+    includes: HashMap<String, String>,
+    /// Missing names already reported, so each is warned about once per build.
+    warned: Mutex<HashSet<String>>,
+    shortcode_re: Regex,
+    arg_re: Regex,
+    include_re: Regex,
+    /// `[build.replacements]`, applied in config order.
+    replacements: Vec<(String, String)>,
+}
+
+impl Shortcodes {
+    pub fn new(config: &Config, theme_dir: &Option<PathBuf>) -> Self {
+        let mut includes = HashMap::new();
+        if let Some(td) = theme_dir {
+            preload(&td.join("includes"), &mut includes);
+        }
+        preload(&config.includes_dir, &mut includes);
+
+        Self {
+            project_dir: config.includes_dir.clone(),
+            includes,
+            warned: Mutex::new(HashSet::new()),
+            shortcode_re: Regex::new(r#"\{\{<\s*([A-Za-z0-9_-]+)((?:\s+[A-Za-z0-9_]+\s*=\s*"[^"]*")*)\s*>\}\}"#).unwrap(),
+            arg_re: Regex::new(r#"([A-Za-z0-9_]+)\s*=\s*"([^"]*)""#).unwrap(),
+            include_re: Regex::new(r#"\{%\s*include\s+"?([^"\s%]+)"?\s*%\}"#).unwrap(),
+            replacements: config.build.replacements.iter()
+                .filter(|(from, _)| !from.is_empty())
+                .map(|(from, to)| (from.clone(), to.clone()))
+                .collect(),
+        }
+    }
+
+    /// Looks up a preloaded include by its path relative to the includes directory.
+    pub fn include(&self, relative: &str) -> Option<&str> {
+        self.includes.get(relative.trim_start_matches("./")).map(String::as_str)
+    }
+
+    /// Warns about every include referenced by `md` (outside fenced code) that exists
+    /// in neither the project nor the theme, returning how many were missing.
+    pub fn check_includes(&self, md: &str, source: &Path) -> usize {
+        let mut missing = 0;
+        for (_, text) in fence_segments(md).into_iter().filter(|&(fenced, _)| !fenced) {
+            for caps in self.include_re.captures_iter(text) {
+                if self.include(&caps[1]).is_none() {
+                    self.warn_include(&caps[1], source);
+                    missing += 1;
+                }
+            }
+        }
+        missing
+    }
+
+    fn warn_include(&self, name: &str, source: &Path) {
+        self.warn_once(
+            &format!("include:{}:{}", source.display(), name),
+            format!("{}: include '{}' not found", source.display(), name),
+        );
+    }
+
+    /// Prints a warning the first time `key` is missing during this build.
+    fn warn_once(&self, key: &str, message: String) {
+        if self.warned.lock().unwrap().insert(key.to_string()) {
+            log::warn!("{}", message);
+        }
+    }
+
+    /// Returns the template source for a shortcode, warning once if it can't be found.
+    fn shortcode_source(&self, name: &str, source: &Path) -> Option<&str> {
+        let found = self.include(&format!("shortcodes/{}.html", name));
+        if found.is_none() {
+            self.warn_once(
+                &format!("shortcode:{}", name),
+                format!(
+                    "{}: shortcode '{}' not found in {} or the theme",
+                    source.display(),
+                    name,
+                    self.project_dir.join("shortcodes").display()
+                ),
+            );
+        }
+        found
+    }
+
+    fn render_shortcode(&self, caps: &Captures, source: &Path) -> String {
+        let name = &caps[1];
+        if name == "include_code" {
+            let args: HashMap<&str, &str> = self.arg_re
+                .captures_iter(&caps[2])
+                .map(|a| (a.get(1).unwrap().as_str(), a.get(2).unwrap().as_str()))
+                .collect();
+            return include_code(&args).unwrap_or_else(|e| {
+                log::error!("{}: include_code: {}", source.display(), e);
+                caps[0].to_string()
+            });
+        }
+        let Some(template) = self.shortcode_source(name, source) else {
+            return caps[0].to_string();
+        };
+        let mut ctx = tera::Context::new();
+        for arg in self.arg_re.captures_iter(&caps[2]) {
+            ctx.insert(&arg[1], &arg[2]);
+        }
+        tera::Tera::one_off(template, &ctx, false).unwrap_or_else(|e| {
+            log::error!("{}: shortcode '{}': {}", source.display(), name, e);
+            String::new()
+        })
+    }
+
+    fn render_include(&self, caps: &Captures, source: &Path) -> String {
+        match self.include(&caps[1]) {
+            Some(contents) => contents.to_string(),
+            None => {
+                self.warn_include(&caps[1], source);
+                caps[0].to_string()
+            }
+        }
+    }
+
+    /// Expands shortcodes, includes and replacements in `md`, skipping fenced code blocks.
+    /// `source` is only used to point warnings at the offending file.
+    pub fn resolve_tags<'a>(&self, md: &'a str, source: &Path) -> Cow<'a, str> {
+        let has_replacements = self.replacements.iter().any(|(from, _)| md.contains(from.as_str()));
+        if !has_replacements && !md.contains("{{<") && !md.contains("{%") {
+            return Cow::Borrowed(md);
+        }
+
+        let mut out = String::with_capacity(md.len());
+        for (fenced, text) in fence_segments(md) {
+            if fenced {
+                out.push_str(text);
+            } else {
+                out.push_str(&self.expand(text, source));
+            }
+        }
+        Cow::Owned(out)
+    }
+
+    fn expand(&self, text: &str, source: &Path) -> String {
+        let mut text = self.include_re.replace_all(text, |caps: &Captures| self.render_include(caps, source));
+        for (from, to) in &self.replacements {
+            if text.contains(from.as_str()) {
+                text = text.replace(from.as_str(), to).into();
+            }
+        }
+        self.shortcode_re
+            .replace_all(&text, |caps: &Captures| self.render_shortcode(caps, source))
+            .into_owned()
+    }
+}
+
+/// Splits `md` into consecutive `(fenced, text)` runs. Fenced runs include their
+/// opening and closing fence lines; an unclosed fence runs to the end of the input.
+fn fence_segments(md: &str) -> Vec<(bool, &str)> {
+    let mut segments = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in md.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+        match (fence, marker) {
+            (None, Some(m)) => {
+                if offset > start {
+                    segments.push((false, &md[start..offset]));
+                }
+                start = offset;
+                fence = Some(m);
+            }
+            (Some(f), Some(m)) if f == m => {
+                segments.push((true, &md[start..offset + line.len()]));
+                start = offset + line.len();
+                fence = None;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    if offset > start {
+        segments.push((fence.is_some(), &md[start..]));
+    }
+    segments
+}
+
+/// Reads `file` (relative to the project root) and wraps the selected part in a
+/// Markdown fence tagged with `lang`, or the file's extension.
+fn include_code(args: &HashMap<&str, &str>) -> Result<String, String> {
+    let file = args.get("file").copied().ok_or("missing file=\"...\" argument")?;
+    let contents = fs::read_to_string(file).map_err(|e| format!("can't read '{}': {}", file, e))?;
+    let lines: Vec<&str> = contents.lines().collect();
+
+    let selected: Vec<&str> = if let Some(region) = args.get("region").copied() {
+        let start = lines.iter()
+            .position(|l| anchor(l, "ANCHOR:") == Some(region))
+            .ok_or_else(|| format!("region '{}' not found in '{}'", region, file))?;
+        let len = lines[start + 1..].iter()
+            .position(|l| anchor(l, "ANCHOR_END:") == Some(region))
+            .ok_or_else(|| format!("region '{}' in '{}' has no ANCHOR_END", region, file))?;
+        lines[start + 1..start + 1 + len].to_vec()
+    } else if let Some(range) = args.get("lines").copied() {
+        let (from, to) = range.split_once('-').unwrap_or((range, range));
+        let from: usize = from.trim().parse().map_err(|_| format!("invalid lines=\"{}\"", range))?;
+        let to: usize = if to.trim().is_empty() {
+            lines.len()
+        } else {
+            to.trim().parse().map_err(|_| format!("invalid lines=\"{}\"", range))?
+        };
+        if from == 0 || from > to || to > lines.len() {
+            return Err(format!("lines {} out of range for '{}' ({} lines)", range, file, lines.len()));
+        }
+        lines[from - 1..to].to_vec()
+    } else {
+        lines
+    };
+
+    // Markers of other regions nested inside the selection aren't part of the sample.
+    let code = selected.into_iter()
+        .filter(|l| anchor(l, "ANCHOR:").is_none() && anchor(l, "ANCHOR_END:").is_none())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let lang = args.get("lang").copied()
+        .or_else(|| Path::new(file).extension().and_then(|e| e.to_str()))
+        .unwrap_or_default();
+    // The fence must be longer than any backtick run inside the code.
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    Ok(format!("\n{fence}{lang}\n{code}\n{fence}\n"))
+}
+
+/// The region name after `marker` in a comment line like `// ANCHOR: setup`.
+fn anchor<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let (_, rest) = line.split_once(marker)?;
+    rest.split_whitespace().next()
+}
+
+/// Reads every text file under `dir` into `into`, keyed by its relative path.
+fn preload(dir: &Path, into: &mut HashMap<String, String>) {
+    if !dir.is_dir() {
+        return;
+    }
+    for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(dir) else { continue };
+        if let Ok(contents) = fs::read_to_string(entry.path()) {
+            let key = relative.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            into.insert(key, contents);
+        }
+    }
+}
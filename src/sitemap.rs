@@ -0,0 +1,31 @@
+//! `sitemap.xml` generation.
+
+use crate::{config::Config, models::Post};
+
+/// Renders a sitemap for the homepage plus every post/page that opts in.
+pub fn generate_sitemap(items: &[&Post], config: &Config) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    xml.push_str(&format!("  <url><loc>{}</loc></url>\n", escape_xml(&config.content_url(""))));
+
+    for p in items.iter().filter(|p| p.in_sitemap(config)) {
+        xml.push_str("  <url>");
+        xml.push_str(&format!("<loc>{}</loc>", escape_xml(&config.permalink_for(p))));
+        xml.push_str(&format!("<lastmod>{}</lastmod>", p.updated.format("%Y-%m-%d")));
+        xml.push_str("</url>\n");
+    }
+
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
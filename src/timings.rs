@@ -0,0 +1,39 @@
+//! Per-phase build durations for `novos build --timings`.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Elapsed time per build phase, in the order phases first ran.
+/// Phases that run more than once (e.g. rendering per language) accumulate.
+#[derive(Default)]
+pub struct Timings {
+    phases: Mutex<Vec<(&'static str, Duration)>>,
+}
+
+impl Timings {
+    /// Adds the time since `started` to `phase`.
+    pub fn add(&self, phase: &'static str, started: Instant) {
+        let elapsed = started.elapsed();
+        if let Ok(mut phases) = self.phases.lock() {
+            match phases.iter_mut().find(|(name, _)| *name == phase) {
+                Some((_, total)) => *total += elapsed,
+                None => phases.push((phase, elapsed)),
+            }
+        }
+    }
+
+    /// Prints every phase with its share of `total`.
+    pub fn report(&self, total: Duration) {
+        let Ok(phases) = self.phases.lock() else { return };
+        log::info!("Build timings:");
+        let total_ms = total.as_secs_f64() * 1000.0;
+        for (phase, elapsed) in phases.iter() {
+            let ms = elapsed.as_secs_f64() * 1000.0;
+            let share = if total_ms > 0.0 { ms / total_ms * 100.0 } else { 0.0 };
+            log::info!("  {:<16} {:>9.1}ms {:>5.1}%", phase, ms, share);
+        }
+        log::info!("  {:<16} {:>9.1}ms", "total", total_ms);
+    }
+}
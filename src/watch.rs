@@ -0,0 +1,118 @@
+//! File watching and debounced rebuilds, shared by `novos serve` and `novos build --watch`.
+use crate::build::{perform_build_incremental, BuildOptions};
+use crate::cache::BuildCache;
+use crate::config::Config;
+use anyhow::Result;
+use console::style;
+use notify::{PollWatcher, Config as WatcherConfig, RecursiveMode, Watcher};
+use std::{path::{Path, PathBuf}, sync::{Arc, Mutex}, time::{SystemTime, Duration}};
+use tokio::sync::{broadcast, mpsc};
+
+/// Paths that never trigger a rebuild: VCS/tooling dirs, editor droppings,
+/// `.gitignore` entries, and the output directory itself.
+pub async fn load_ignore_list(config: &Config) -> Vec<String> {
+    let mut ignore_list = vec![
+        ".git".to_string(), 
+        "target".to_string(), 
+        "#".to_string(), 
+        ".swp".to_string(),
+        crate::cache::STATE_DIR.to_string(),
+    ];
+
+    let output = config.output_dir.to_string_lossy();
+    let output = output.trim_start_matches("./").trim_end_matches('/');
+    if !output.is_empty() && output != "." {
+        ignore_list.push(output.to_string());
+    }
+    
+    if let Ok(gc) = tokio::fs::read_to_string(".gitignore").await {
+        for line in gc.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            ignore_list.push(line.to_string()); // Convert to owned String
+        }
+    }
+    ignore_list
+}
+
+/// Spawns the async build worker. Every message on the returned channel carries changed
+/// paths and schedules a rebuild; bursts are debounced into one incremental rebuild over
+/// all of their paths. Successful rebuilds are announced on `notify`.
+pub fn spawn_rebuild_worker(
+    config: Config,
+    last_run: Arc<Mutex<SystemTime>>,
+    cache: Arc<BuildCache>,
+    opts: BuildOptions,
+    notify: Option<broadcast::Sender<()>>,
+) -> mpsc::Sender<Vec<PathBuf>> {
+    let (event_tx, mut event_rx) = mpsc::channel::<Vec<PathBuf>>(100);
+    tokio::spawn(async move {
+        while let Some(mut changed) = event_rx.recv().await {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            while let Ok(more) = event_rx.try_recv() {
+                changed.extend(more);
+            }
+            changed.sort();
+            changed.dedup();
+            log::info!("{} Change detected, rebuilding...", style("[novos]").green());
+            let started = std::time::Instant::now();
+            match perform_build_incremental(&config, Arc::clone(&last_run), &cache, &opts, &changed) {
+                Ok(()) => {
+                    log::debug!("  rebuilt in {}ms", started.elapsed().as_millis());
+                    if let Some(tx) = &notify {
+                        let _ = tx.send(());
+                    }
+                }
+                Err(e) => log::error!("rebuild failed: {}", e),
+            }
+        }
+    });
+    event_tx
+}
+
+/// Starts polling the project root, forwarding relevant changes to `event_tx`.
+/// The returned watcher must be kept alive for as long as watching should continue.
+pub fn start_watcher(
+    ignore_list: Vec<String>,
+    event_tx: mpsc::Sender<Vec<PathBuf>>,
+    poll_interval: Duration,
+) -> Result<PollWatcher> {
+    // PollWatcher rather than the native backend: panic-proof on FreeBSD
+    let watch_config = WatcherConfig::default().with_poll_interval(poll_interval);
+    
+    let mut watcher = PollWatcher::new(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            let relevant: Vec<PathBuf> = event.paths.into_iter().filter(|p| {
+                let s = p.to_string_lossy();
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                
+                let is_ignored = ignore_list.iter().any(|ig| s.contains(ig)) 
+                                || name.starts_with('.') 
+                                || name.starts_with('#') 
+                                || name.ends_with('~');
+                !is_ignored
+            }).collect();
+
+            if !relevant.is_empty() && (event.kind.is_modify() || event.kind.is_create()) {
+                let _ = event_tx.try_send(relevant);
+            }
+        }
+    }, watch_config)?;
+
+    watcher.watch(Path::new("."), RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// `novos build --watch`: rebuild on change without serving or live-reload injection.
+/// Rebuilds reuse the initial build's `opts`, so `--preview`, `--strict` and `--timings`
+/// hold for every rebuild.
+pub async fn watch(config: Config, last_run: Arc<Mutex<SystemTime>>, opts: BuildOptions) -> Result<()> {
+    let ignore_list = load_ignore_list(&config).await;
+    let cache = Arc::new(BuildCache::default());
+    let poll_interval = Duration::from_millis(config.server.poll_interval);
+
+    let event_tx = spawn_rebuild_worker(config, last_run, cache, opts, None);
+    let _watcher = start_watcher(ignore_list, event_tx, poll_interval)?;
+
+    log::info!("{}", style(" novos watching for changes (Ctrl+C to stop)").yellow());
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}